@@ -0,0 +1,224 @@
+use std::fmt;
+
+use crate::json;
+use crate::lexer::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number { value: i32, span: Span },
+    /// `syscall(num, a, b, ...)`: traps into the kernel with the syscall
+    /// number first and up to six arguments, evaluating to the result.
+    Syscall { args: Vec<Expr>, span: Span },
+    /// `waqt()`: the current wall-clock time in whole seconds since the Unix
+    /// epoch, read with `clock_gettime(CLOCK_REALTIME)`.
+    Time { span: Span },
+    /// `chghel("/bin/prog")`: forks, runs the program with `execve` and
+    /// waits for it, evaluating to its exit status. A program that cannot be
+    /// started exits with 127, like in a shell.
+    Spawn { path: String, span: Span },
+    /// `lhs op rhs` on signed 64-bit integers, wrapping on overflow.
+    Binary { op: BinOp, lhs: Box<Expr>, rhs: Box<Expr>, span: Span },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    /// Truncating division. Dividing by zero gives 0.
+    Div,
+    /// Remainder with the sign of `lhs`. The remainder of dividing by zero
+    /// is `lhs`, so `lhs == (lhs / rhs) * rhs + lhs % rhs` always holds.
+    Rem,
+}
+
+impl BinOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+        }
+    }
+}
+
+/// The syscall number plus the six argument registers every supported
+/// target provides.
+pub const MAX_SYSCALL_ARGS: usize = 7;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Return { value: Expr, span: Span },
+}
+
+/// A program runs its statements in order and leaves at the first `kharrej`.
+/// One without statements falls off the end, which exits with 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub statements: Vec<Stmt>,
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number { value, .. } => write!(f, "{}", value),
+            Expr::Syscall { args, .. } => {
+                let args: Vec<String> = args.iter().map(Expr::to_string).collect();
+                write!(f, "syscall({})", args.join(", "))
+            }
+            Expr::Time { .. } => write!(f, "waqt()"),
+            Expr::Spawn { path, .. } => write!(f, "chghel({:?})", path),
+            Expr::Binary { op, lhs, rhs, .. } => write!(f, "({} {} {})", lhs, op.symbol(), rhs),
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Return { value, .. } => write!(f, "kharrej {};", value),
+        }
+    }
+}
+
+fn node(head: &str, children: Vec<String>, depth: usize) -> String {
+    let mut out = format!("({}", head);
+    for child in children {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth + 1));
+        out.push_str(&child);
+    }
+    out.push(')');
+    out
+}
+
+fn span_json(span: &Span) -> String {
+    format!("{{\"start\": {}, \"end\": {}}}", span.start, span.end)
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Number { span, .. }
+            | Expr::Syscall { span, .. }
+            | Expr::Time { span }
+            | Expr::Spawn { span, .. }
+            | Expr::Binary { span, .. } => *span,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        match self {
+            Expr::Number { .. } | Expr::Time { .. } | Expr::Spawn { .. } => 1,
+            Expr::Syscall { args, .. } => 1 + args.iter().map(Expr::node_count).sum::<usize>(),
+            Expr::Binary { lhs, rhs, .. } => 1 + lhs.node_count() + rhs.node_count(),
+        }
+    }
+
+    fn sexpr(&self, depth: usize) -> String {
+        match self {
+            Expr::Number { value, .. } => node(&format!("number {}", value), Vec::new(), depth),
+            Expr::Syscall { args, .. } => {
+                let children = args.iter().map(|arg| arg.sexpr(depth + 1)).collect();
+                node("syscall", children, depth)
+            }
+            Expr::Time { .. } => node("waqt", Vec::new(), depth),
+            Expr::Spawn { path, .. } => node(&format!("chghel {:?}", path), Vec::new(), depth),
+            Expr::Binary { op, lhs, rhs, .. } => {
+                node(op.symbol(), vec![lhs.sexpr(depth + 1), rhs.sexpr(depth + 1)], depth)
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            Expr::Number { value, span } => format!(
+                "{{\"kind\": {}, \"value\": {}, \"span\": {}}}",
+                json::string("number"),
+                value,
+                span_json(span)
+            ),
+            Expr::Syscall { args, span } => {
+                let args: Vec<String> = args.iter().map(Expr::to_json).collect();
+                format!(
+                    "{{\"kind\": {}, \"args\": [{}], \"span\": {}}}",
+                    json::string("syscall"),
+                    args.join(", "),
+                    span_json(span)
+                )
+            }
+            Expr::Time { span } => format!(
+                "{{\"kind\": {}, \"span\": {}}}",
+                json::string("waqt"),
+                span_json(span)
+            ),
+            Expr::Spawn { path, span } => format!(
+                "{{\"kind\": {}, \"path\": {}, \"span\": {}}}",
+                json::string("chghel"),
+                json::string(path),
+                span_json(span)
+            ),
+            Expr::Binary { op, lhs, rhs, span } => format!(
+                "{{\"kind\": {}, \"op\": {}, \"lhs\": {}, \"rhs\": {}, \"span\": {}}}",
+                json::string("binary"),
+                json::string(op.symbol()),
+                lhs.to_json(),
+                rhs.to_json(),
+                span_json(span)
+            ),
+        }
+    }
+}
+
+impl Stmt {
+    pub fn node_count(&self) -> usize {
+        match self {
+            Stmt::Return { value, .. } => 1 + value.node_count(),
+        }
+    }
+
+    fn sexpr(&self, depth: usize) -> String {
+        match self {
+            Stmt::Return { value, .. } => node("kharrej", vec![value.sexpr(depth + 1)], depth),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            Stmt::Return { value, span } => format!(
+                "{{\"kind\": {}, \"value\": {}, \"span\": {}}}",
+                json::string("return"),
+                value.to_json(),
+                span_json(span)
+            ),
+        }
+    }
+}
+
+impl Program {
+    pub fn node_count(&self) -> usize {
+        1 + self.statements.iter().map(Stmt::node_count).sum::<usize>()
+    }
+
+    /// Renders the tree as an indented S-expression, one node per line.
+    pub fn to_sexpr(&self) -> String {
+        let children = self.statements.iter().map(|stmt| stmt.sexpr(1)).collect();
+        node("program", children, 0)
+    }
+
+    /// Renders the tree as JSON with one statement per line; every node
+    /// carries a `kind` and the byte span it covers.
+    pub fn to_json(&self) -> String {
+        let statements: Vec<String> =
+            self.statements.iter().map(|stmt| format!("    {}", stmt.to_json())).collect();
+        if statements.is_empty() {
+            return "{\"kind\": \"program\", \"statements\": []}".to_string();
+        }
+        format!(
+            "{{\n  \"kind\": \"program\",\n  \"statements\": [\n{}\n  ]\n}}",
+            statements.join(",\n")
+        )
+    }
+}
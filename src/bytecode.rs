@@ -0,0 +1,233 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Program, Stmt};
+
+/// Every `.riac` file starts with these bytes followed by a format version.
+const MAGIC: &[u8; 4] = b"RIAC";
+const VERSION: u8 = 2;
+
+const OP_PUSH: u8 = 0x01;
+const OP_RET: u8 = 0x02;
+const OP_SYSCALL: u8 = 0x03;
+const OP_TIME: u8 = 0x04;
+const OP_SPAWN: u8 = 0x05;
+
+/// Each binary operator has its own opcode.
+const BINARY_OPCODES: [(u8, BinOp); 5] = [
+    (0x06, BinOp::Add),
+    (0x07, BinOp::Sub),
+    (0x08, BinOp::Mul),
+    (0x09, BinOp::Div),
+    (0x0a, BinOp::Rem),
+];
+
+unsafe extern "C" {
+    fn syscall(number: i64, ...) -> i64;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    /// Push a 32-bit immediate onto the stack.
+    Push(i32),
+    /// Pop the top of the stack and leave the program with it as exit code.
+    Ret,
+    /// Pop this many operands (the syscall number was pushed first), make the
+    /// syscall on the host and push its result.
+    Syscall(u8),
+    /// Push the current Unix time in seconds.
+    Time,
+    /// Run the program at this path, wait for it and push its exit status.
+    Spawn(String),
+    /// Pop the right and then the left operand and push the result.
+    Binary(BinOp),
+}
+
+pub fn compile(program: &Program) -> Vec<Instr> {
+    let mut code = Vec::new();
+
+    for stmt in &program.statements {
+        match stmt {
+            Stmt::Return { value, .. } => {
+                compile_expr(value, &mut code);
+                code.push(Instr::Ret);
+            }
+        }
+    }
+    if program.statements.is_empty() {
+        code.extend([Instr::Push(0), Instr::Ret]);
+    }
+
+    code
+}
+
+fn compile_expr(expr: &Expr, code: &mut Vec<Instr>) {
+    match expr {
+        Expr::Number { value, .. } => code.push(Instr::Push(*value)),
+        Expr::Syscall { args, .. } => {
+            for arg in args {
+                compile_expr(arg, code);
+            }
+            code.push(Instr::Syscall(args.len() as u8));
+        }
+        Expr::Time { .. } => code.push(Instr::Time),
+        Expr::Spawn { path, .. } => code.push(Instr::Spawn(path.clone())),
+        Expr::Binary { op, lhs, rhs, .. } => {
+            compile_expr(lhs, code);
+            compile_expr(rhs, code);
+            code.push(Instr::Binary(*op));
+        }
+    }
+}
+
+pub fn encode(code: &[Instr]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+
+    for instr in code {
+        match instr {
+            Instr::Push(value) => {
+                bytes.push(OP_PUSH);
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Instr::Ret => bytes.push(OP_RET),
+            Instr::Syscall(count) => bytes.extend_from_slice(&[OP_SYSCALL, *count]),
+            Instr::Time => bytes.push(OP_TIME),
+            Instr::Spawn(path) => {
+                bytes.push(OP_SPAWN);
+                bytes.extend_from_slice(&(path.len() as u64).to_le_bytes());
+                bytes.extend_from_slice(path.as_bytes());
+            }
+            Instr::Binary(op) => {
+                let (opcode, _) = BINARY_OPCODES.iter().find(|(_, o)| o == op).unwrap();
+                bytes.push(*opcode);
+            }
+        }
+    }
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<Instr>, String> {
+    let body = bytes
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| "not a .riac file (bad magic)".to_string())?;
+    let (&version, mut body) = body
+        .split_first()
+        .ok_or_else(|| "truncated header".to_string())?;
+    if version != VERSION {
+        return Err(format!("unsupported bytecode version {}", version));
+    }
+
+    let mut code = Vec::new();
+    while let Some((&op, rest)) = body.split_first() {
+        body = rest;
+        match op {
+            OP_PUSH => {
+                let (imm, rest) = body
+                    .split_first_chunk::<4>()
+                    .ok_or_else(|| "truncated push operand".to_string())?;
+                code.push(Instr::Push(i32::from_le_bytes(*imm)));
+                body = rest;
+            }
+            OP_RET => code.push(Instr::Ret),
+            OP_SYSCALL => {
+                let (&count, rest) = body
+                    .split_first()
+                    .ok_or_else(|| "truncated syscall operand".to_string())?;
+                if count == 0 || count as usize > MAX_SYSCALL_ARGS {
+                    return Err(format!("syscall with {} operands", count));
+                }
+                code.push(Instr::Syscall(count));
+                body = rest;
+            }
+            OP_TIME => code.push(Instr::Time),
+            OP_SPAWN => {
+                let (len, rest) = body
+                    .split_first_chunk::<8>()
+                    .ok_or_else(|| "truncated spawn operand".to_string())?;
+                let len = u64::from_le_bytes(*len);
+                let Some(len) = usize::try_from(len).ok().filter(|&len| len <= rest.len()) else {
+                    return Err("truncated spawn operand".to_string());
+                };
+                let path = String::from_utf8(rest[..len].to_vec())
+                    .map_err(|_| "spawn path is not UTF-8".to_string())?;
+                code.push(Instr::Spawn(path));
+                body = &rest[len..];
+            }
+            other => match BINARY_OPCODES.iter().find(|(opcode, _)| *opcode == other) {
+                Some(&(_, op)) => code.push(Instr::Binary(op)),
+                None => return Err(format!("unknown opcode 0x{:02x}", other)),
+            },
+        }
+    }
+
+    Ok(code)
+}
+
+/// Makes the syscall through libc, translating its `-1` + `errno` convention
+/// back into the `-errno` the raw instruction returns in native builds.
+fn host_syscall(operands: [i64; MAX_SYSCALL_ARGS]) -> i64 {
+    let [number, a, b, c, d, e, f] = operands;
+    let result = unsafe { syscall(number, a, b, c, d, e, f) };
+    if result == -1 {
+        -(std::io::Error::last_os_error().raw_os_error().unwrap_or(0) as i64)
+    } else {
+        result
+    }
+}
+
+/// Interprets the instructions on an operand stack and returns the exit code
+/// of the first `Ret` that executes. Values are 64 bits wide, like the
+/// registers native code computes in.
+pub fn run(code: &[Instr]) -> Result<i32, String> {
+    let mut stack: Vec<i64> = Vec::new();
+
+    for instr in code {
+        match instr {
+            Instr::Push(value) => stack.push(*value as i64),
+            Instr::Ret => {
+                let value = stack.pop().ok_or_else(|| "stack underflow".to_string())?;
+                return Ok(value as i32);
+            }
+            Instr::Syscall(count) => {
+                let base = stack
+                    .len()
+                    .checked_sub(*count as usize)
+                    .ok_or_else(|| "stack underflow".to_string())?;
+                let mut operands = [0i64; MAX_SYSCALL_ARGS];
+                for (slot, value) in operands.iter_mut().zip(stack.drain(base..)) {
+                    *slot = value;
+                }
+                stack.push(host_syscall(operands));
+            }
+            Instr::Time => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                stack.push(now.as_secs() as i64);
+            }
+            Instr::Spawn(path) => {
+                // Mirror the native code: an empty environment, and 127 when
+                // the program cannot be started.
+                let status = Command::new(path).env_clear().status();
+                stack.push(status.map_or(127, |status| status.code().unwrap_or(0)) as i64);
+            }
+            Instr::Binary(op) => {
+                let (rhs, lhs) = stack
+                    .pop()
+                    .zip(stack.pop())
+                    .ok_or_else(|| "stack underflow".to_string())?;
+                stack.push(match op {
+                    BinOp::Add => lhs.wrapping_add(rhs),
+                    BinOp::Sub => lhs.wrapping_sub(rhs),
+                    BinOp::Mul => lhs.wrapping_mul(rhs),
+                    BinOp::Div if rhs == 0 => 0,
+                    BinOp::Div => lhs.wrapping_div(rhs),
+                    BinOp::Rem if rhs == 0 => lhs,
+                    BinOp::Rem => lhs.wrapping_rem(rhs),
+                });
+            }
+        }
+    }
+
+    Err("fell off the end of the program without returning".to_string())
+}
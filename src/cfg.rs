@@ -0,0 +1,69 @@
+use std::fmt::Write;
+
+use crate::ast::{Program, Stmt};
+
+/// A straight-line run of statements. Every block currently ends by leaving
+/// the program, since `kharrej` is the only way to transfer control.
+#[derive(Debug)]
+pub struct BasicBlock<'a> {
+    pub statements: Vec<&'a Stmt>,
+    pub reachable: bool,
+}
+
+#[derive(Debug)]
+pub struct Cfg<'a> {
+    pub blocks: Vec<BasicBlock<'a>>,
+}
+
+impl<'a> Cfg<'a> {
+    pub fn build(program: &'a Program) -> Self {
+        let mut blocks = Vec::new();
+        let mut current = Vec::new();
+
+        for stmt in &program.statements {
+            current.push(stmt);
+            match stmt {
+                Stmt::Return { .. } => {
+                    blocks.push(BasicBlock {
+                        statements: std::mem::take(&mut current),
+                        reachable: blocks.is_empty(),
+                    });
+                }
+            }
+        }
+
+        Cfg { blocks }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        out.push_str("digraph cfg {\n");
+        out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        out.push_str("    entry [shape=oval];\n");
+        out.push_str("    exit [shape=oval];\n");
+
+        if self.blocks.is_empty() {
+            out.push_str("    entry -> exit;\n");
+        } else {
+            out.push_str("    entry -> bb0;\n");
+        }
+
+        for (id, block) in self.blocks.iter().enumerate() {
+            let mut label = format!("bb{}:", id);
+            if !block.reachable {
+                label.push_str(" (unreachable)");
+            }
+            for stmt in &block.statements {
+                let _ = write!(label, "\\l{}", stmt);
+            }
+            label.push_str("\\l");
+
+            let style = if block.reachable { "" } else { ", style=dashed" };
+            let _ = writeln!(out, "    bb{} [label=\"{}\"{}];", id, label, style);
+            let _ = writeln!(out, "    bb{} -> exit;", id);
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
@@ -0,0 +1,442 @@
+use std::time::Duration;
+
+use crate::limits::Limits;
+use crate::target::Target;
+use crate::toolchain::{LinkMode, LinkOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    Cfg,
+    LlvmIr,
+    Bytecode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub enum OptLevel {
+    #[default]
+    O0,
+    O1,
+    O2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrateType {
+    Bin,
+    Staticlib,
+    /// A `lib<name>.so` shared library.
+    Cdylib,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub input: String,
+    pub emit: Option<Emit>,
+    pub format: Format,
+    pub target: Target,
+    pub opt_level: OptLevel,
+    pub optimize_size: bool,
+    pub crate_type: CrateType,
+    pub coverage: bool,
+    pub time_passes: bool,
+    pub stats: bool,
+    /// Where to write the per-build JSON report, if anywhere.
+    pub stats_json: Option<String>,
+    pub jit: bool,
+    /// Directories searched for `jme3` includes, in order.
+    pub include_paths: Vec<String>,
+    /// `--cfg` flags that `#ila` sections can test.
+    pub cfg_flags: Vec<String>,
+    /// Shared libraries to load as compiler plugins, in order.
+    pub plugins: Vec<String>,
+    pub link: LinkOptions,
+    /// Run the executable after a successful build.
+    pub run: bool,
+    /// Kill the executable `run` starts if it runs longer than this.
+    pub timeout: Option<Duration>,
+    /// Arguments after `--`, passed on to the executable `run` starts.
+    pub program_args: Vec<String>,
+    /// Print what the build would run and produce instead of building.
+    pub build_plan: bool,
+    /// Stop after writing `<name>.o`, leaving linking to `link`.
+    pub compile_only: bool,
+    /// Overwrite outputs that already exist.
+    pub force: bool,
+    pub limits: Limits,
+}
+
+/// Objects to link into one executable with `link`.
+#[derive(Debug, Clone)]
+pub struct LinkJob {
+    pub objects: Vec<String>,
+    pub output: String,
+    pub target: Target,
+    pub link: LinkOptions,
+}
+
+#[derive(Debug, Clone)]
+pub enum Invocation {
+    Explain(String),
+    /// Print the coverage report for the input of these options.
+    CoverageReport(Box<Options>),
+    RunBytecode(String),
+    /// Create a project directory with this name.
+    New(String),
+    /// Remove the build outputs of the project in the current directory.
+    Clean,
+    Link(Box<LinkJob>),
+    /// Report where the tools for building for this target are found.
+    ToolchainCheck(Target),
+    Compile(Box<Options>),
+}
+
+/// Where flags from configuration files go in `args` so that the command
+/// line overrides them: right after `build` or `run`, or before everything
+/// else. `None` for the subcommands that do not build.
+pub fn defaults_position(args: &[String]) -> Option<usize> {
+    match args.get(1).map(String::as_str) {
+        Some("build" | "run") => Some(2),
+        Some("cov") if args.len() > 2 => Some(3),
+        Some("cov" | "new" | "clean" | "link" | "toolchain") => None,
+        _ => Some(1),
+    }
+}
+
+fn expect_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("Missing value for '{}'", flag))
+}
+
+/// Applies `arg` to `link` if it is one of the flags that configure the
+/// linker, which builds and `link` share. Returns whether it was.
+fn parse_link_flag(
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+    link: &mut LinkOptions,
+) -> Result<bool, String> {
+    match arg {
+        "--pie" => link.pie = true,
+        "--link" => {
+            link.mode = match expect_value(args, "--link")?.as_str() {
+                "static" => LinkMode::Static,
+                "dynamic" => LinkMode::Dynamic,
+                other => return Err(format!("Unknown link mode '{}'", other)),
+            };
+        }
+        "--strip" => link.strip = true,
+        "-L" => link.search_paths.push(expect_value(args, "-L")?),
+        "-l" => link.libraries.push(expect_value(args, "-l")?),
+        "--link-arg" => link.extra_args.push(expect_value(args, "--link-arg")?),
+        flag if flag.starts_with("-L") => link.search_paths.push(flag[2..].to_string()),
+        flag if flag.starts_with("-l") => link.libraries.push(flag[2..].to_string()),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Parses `link <objects...> [-o <output>] [options]`.
+fn parse_link(mut args: impl Iterator<Item = String>) -> Result<LinkJob, String> {
+    let mut objects = Vec::new();
+    let mut output = None;
+    let mut target = Target::host();
+    let mut link = LinkOptions::default();
+
+    while let Some(arg) = args.next() {
+        if parse_link_flag(&arg, &mut args, &mut link)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-o" => output = Some(expect_value(&mut args, "-o")?),
+            "--target" => target = Target::parse(&expect_value(&mut args, "--target")?)?,
+            flag if flag.starts_with('-') => return Err(format!("Unknown option '{}'", flag)),
+            _ if is_object_file(&arg) => objects.push(arg),
+            _ => return Err(format!("Expected an object file or archive, got '{}'", arg)),
+        }
+    }
+
+    if objects.is_empty() {
+        return Err("Expected 'link <objects...> -o <output>'".to_string());
+    }
+    Ok(LinkJob {
+        objects,
+        output: output.unwrap_or_else(|| "a.out".to_string()),
+        target,
+        link,
+    })
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Invocation, String> {
+    let mut args = args.into_iter().skip(1).peekable();
+    let mut run = true;
+    let mut report = false;
+    match args.peek().map(String::as_str) {
+        Some("build") => {
+            args.next();
+            run = false;
+        }
+        Some("run") => {
+            args.next();
+        }
+        Some("cov") => {
+            args.next();
+            if args.next().as_deref() != Some("report") {
+                return Err("Expected 'cov report <input.ria> [options]'".to_string());
+            }
+            report = true;
+            run = false;
+        }
+        Some("new") => {
+            args.next();
+            return match (args.next(), args.next()) {
+                (Some(name), None) => Ok(Invocation::New(name)),
+                _ => Err("Expected 'new <name>'".to_string()),
+            };
+        }
+        Some("clean") => {
+            args.next();
+            return match args.next() {
+                None => Ok(Invocation::Clean),
+                Some(arg) => Err(format!("Unexpected argument '{}'", arg)),
+            };
+        }
+        Some("link") => {
+            args.next();
+            return parse_link(args).map(|job| Invocation::Link(Box::new(job)));
+        }
+        Some("toolchain") => {
+            args.next();
+            let args: Vec<String> = args.collect();
+            let target = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["check"] => Target::host(),
+                ["check", "--target", triple] => Target::parse(triple)?,
+                _ => return Err("Expected 'toolchain check [--target <triple>]'".to_string()),
+            };
+            return Ok(Invocation::ToolchainCheck(target));
+        }
+        _ => {}
+    }
+
+    let mut input = None;
+    let mut emit = None;
+    let mut format = Format::Text;
+    let mut target = Target::host();
+    let mut opt_level = OptLevel::default();
+    let mut optimize_size = false;
+    let mut crate_type = CrateType::Bin;
+    let mut coverage = false;
+    let mut time_passes = false;
+    let mut stats = false;
+    let mut stats_json = None;
+    let mut jit = false;
+    let mut build_plan = false;
+    let mut compile_only = false;
+    let mut force = false;
+    let mut limits = Limits::default();
+    let mut timeout = None;
+    let mut program_args = Vec::new();
+    let mut include_paths = Vec::new();
+    let mut cfg_flags = Vec::new();
+    let mut plugins = Vec::new();
+    let mut link = LinkOptions::default();
+
+    while let Some(arg) = args.next() {
+        if parse_link_flag(&arg, &mut args, &mut link)? {
+            continue;
+        }
+        match arg.as_str() {
+            "--explain" => {
+                return Ok(Invocation::Explain(expect_value(&mut args, "--explain")?));
+            }
+            "--emit" => {
+                emit = Some(match expect_value(&mut args, "--emit")?.as_str() {
+                    "tokens" => Emit::Tokens,
+                    "ast" => Emit::Ast,
+                    "cfg" => Emit::Cfg,
+                    "llvm-ir" => Emit::LlvmIr,
+                    "bytecode" => Emit::Bytecode,
+                    other => return Err(format!("Unknown emit kind '{}'", other)),
+                });
+            }
+            "--format" => {
+                format = match expect_value(&mut args, "--format")?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => return Err(format!("Unknown format '{}'", other)),
+                };
+            }
+            "--coverage" => coverage = true,
+            "--time-passes" => time_passes = true,
+            "--stats" => stats = true,
+            "--stats-json" => stats_json = Some(expect_value(&mut args, "--stats-json")?),
+            "--jit" => jit = true,
+            "--build-plan" => build_plan = true,
+            "--" => program_args.extend(args.by_ref()),
+            "-c" => compile_only = true,
+            "--force" => force = true,
+            "--limit" => limits.set(&expect_value(&mut args, "--limit")?)?,
+            "--timeout" => {
+                let value = expect_value(&mut args, "--timeout")?;
+                timeout = match value.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 && seconds.is_finite() => {
+                        Some(Duration::from_secs_f64(seconds))
+                    }
+                    _ => return Err(format!("Invalid timeout '{}': expected seconds", value)),
+                };
+            }
+            "--cfg" => cfg_flags.push(expect_value(&mut args, "--cfg")?),
+            "--plugin" => plugins.push(expect_value(&mut args, "--plugin")?),
+            "--target" => target = Target::parse(&expect_value(&mut args, "--target")?)?,
+            "-O0" => (opt_level, optimize_size) = (OptLevel::O0, false),
+            "-O1" => (opt_level, optimize_size) = (OptLevel::O1, false),
+            "-O2" => (opt_level, optimize_size) = (OptLevel::O2, false),
+            "-Os" => optimize_size = true,
+            "--crate-type" => {
+                crate_type = match expect_value(&mut args, "--crate-type")?.as_str() {
+                    "bin" => CrateType::Bin,
+                    "staticlib" => CrateType::Staticlib,
+                    "cdylib" => CrateType::Cdylib,
+                    other => return Err(format!("Unknown crate type '{}'", other)),
+                };
+            }
+            "-I" => include_paths.push(expect_value(&mut args, "-I")?),
+            flag if flag.starts_with("-I") => include_paths.push(flag[2..].to_string()),
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'", flag));
+            }
+            _ if is_object_file(&arg) => link.objects.push(arg),
+            _ => {
+                if input.is_some() {
+                    return Err(format!("Unexpected argument '{}'", arg));
+                }
+                input = Some(arg);
+            }
+        }
+    }
+
+    let input = input.ok_or_else(|| "No input file given".to_string())?;
+
+    if optimize_size {
+        link.strip = true;
+    }
+
+    if jit && !run {
+        return Err("--jit only applies to 'run'".to_string());
+    }
+
+    if timeout.is_some() && (!run || jit || input.ends_with(".riac")) {
+        return Err("--timeout only applies to 'run' of a native executable".to_string());
+    }
+
+    if !program_args.is_empty() && (!run || jit || input.ends_with(".riac")) {
+        return Err("Arguments after '--' only apply to 'run' of a native executable".to_string());
+    }
+
+    if build_plan && (emit.is_some() || jit) {
+        return Err("--build-plan cannot be combined with --emit or --jit".to_string());
+    }
+
+    if compile_only {
+        if run {
+            return Err("-c only applies to 'build'".to_string());
+        }
+        if emit.is_some() || crate_type != CrateType::Bin || !link.objects.is_empty() {
+            return Err(
+                "-c cannot be combined with --emit, --crate-type or objects to link".to_string(),
+            );
+        }
+    }
+
+    if input.ends_with(".riac") {
+        if !run {
+            return Err("bytecode files can only be run, not built".to_string());
+        }
+        return Ok(Invocation::RunBytecode(input));
+    }
+
+    let options = Box::new(Options {
+        input,
+        emit,
+        format,
+        target,
+        opt_level,
+        optimize_size,
+        crate_type,
+        coverage,
+        time_passes,
+        stats,
+        stats_json,
+        jit,
+        include_paths,
+        cfg_flags,
+        plugins,
+        link,
+        run: run && crate_type == CrateType::Bin && target.is_host(),
+        timeout,
+        program_args,
+        build_plan,
+        compile_only,
+        force,
+        limits,
+    });
+    Ok(if report { Invocation::CoverageReport(options) } else { Invocation::Compile(options) })
+}
+
+fn is_object_file(arg: &str) -> bool {
+    arg.ends_with(".o") || arg.ends_with(".a")
+}
+
+pub fn print_usage() {
+    eprintln!("Correct usage is:");
+    eprintln!("   zakaria <input.ria> [options]");
+    eprintln!("   zakaria build <input.ria> [objects...] [options]");
+    eprintln!("   zakaria build -c <input.ria> [options]");
+    eprintln!("   zakaria link <objects...> [-o <output>] [link options]");
+    eprintln!("   zakaria run <input.ria> [--jit] [options] [-- <program arguments...>]");
+    eprintln!("   zakaria run <input.riac>");
+    eprintln!("   zakaria cov report <input.ria> [options]");
+    eprintln!("   zakaria new <name>");
+    eprintln!("   zakaria clean");
+    eprintln!("   zakaria toolchain check [--target <triple>]");
+    eprintln!("   zakaria --explain <code>");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("   --emit tokens|ast      Print the token stream or syntax tree and stop");
+    eprintln!("   --emit cfg             Write the control-flow graph to <name>.dot and stop");
+    eprintln!("   --emit llvm-ir         Print the program as an LLVM IR module and stop");
+    eprintln!("   --emit bytecode        Write portable VM bytecode to <name>.riac and stop");
+    eprintln!("   --format text|json     Output format for --emit (default: text)");
+    eprintln!("   --pie                  Build a position-independent executable");
+    eprintln!("   --link static|dynamic  How the executable is linked (default: static)");
+    eprintln!("   --strip                Remove symbols from the executable");
+    eprintln!("   --crate-type bin|staticlib|cdylib");
+    eprintln!("                          Build an executable, a lib<name>.a archive or a");
+    eprintln!("                          lib<name>.so shared library");
+    eprintln!("   --target <triple>      Cross-compile, e.g. aarch64-unknown-linux-gnu or");
+    eprintln!("                          riscv64gc-unknown-linux-gnu");
+    eprintln!("   -O0|-O1|-O2            Optimization level (default: -O0)");
+    eprintln!("   -Os                    Optimize for size: compact assembly and --strip");
+    eprintln!("   --coverage             Count statement executions into <name>.cov");
+    eprintln!("   --time-passes          Report how long each compiler pass took");
+    eprintln!("   --stats                Print compiler statistics as JSON");
+    eprintln!("   --stats-json <path>    Write build times, diagnostics and artifact sizes");
+    eprintln!("                          to <path> as JSON");
+    eprintln!("   --jit                  With 'run', execute in memory without nasm or ld");
+    eprintln!("   --timeout <seconds>    With 'run', kill the program if it runs longer");
+    eprintln!("   --build-plan           Print the build's commands and outputs as JSON and stop");
+    eprintln!("   -c                     With 'build', stop at <name>.o instead of linking");
+    eprintln!("   --force                Overwrite outputs that already exist");
+    eprintln!("   --limit <name>=<n>     Raise or lower a limit on the input: file-size (bytes),");
+    eprintln!("                          tokens or depth");
+    eprintln!("   -I <dir>               Add a directory to search for jme3 includes");
+    eprintln!("   --cfg <name[=value]>   Set a flag that #ila sections can test");
+    eprintln!("   --plugin <lib.so>      Load a compiler plugin (lints or assembly passes)");
+    eprintln!("   -L <dir>               Add a library search path for the linker");
+    eprintln!("   -l <name>              Link against a library");
+    eprintln!("   --link-arg <arg>       Pass an argument straight to the linker");
+}
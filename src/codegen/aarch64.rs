@@ -0,0 +1,147 @@
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Program, Stmt};
+
+use super::{CodegenOptions, Context, LIBRARY_ENTRY};
+
+fn emit(asm_code: &mut String, options: &CodegenOptions, instruction: &str, comment: &str) {
+    super::emit_line(asm_code, options, "//", instruction, comment);
+}
+
+/// Generates GNU as syntax for 64-bit ARM Linux, where the syscall number
+/// goes in `x8`, the first argument in `x0`, and `svc #0` traps.
+pub fn generate(program: &Program, options: &CodegenOptions) -> String {
+    let mut asm_code = String::new();
+    let mut ctx = Context::default();
+
+    let entry = if options.library { LIBRARY_ENTRY } else { "_start" };
+    asm_code.push_str(&format!(".global {}\n", entry));
+    asm_code.push_str(".text\n");
+    asm_code.push_str(&format!("{}:\n", entry));
+
+    for stmt in &program.statements {
+        match stmt {
+            Stmt::Return { value: Expr::Number { value, .. }, .. } => {
+                emit(&mut asm_code, options, "mov x8, #93", "sys_exit");
+                emit(&mut asm_code, options, &format!("mov x0, #{}", value), "exit code");
+                asm_code.push_str("    svc #0\n");
+            }
+            Stmt::Return { value, .. } => {
+                emit_expr(&mut asm_code, options, value, &mut ctx);
+                emit(&mut asm_code, options, "mov x8, #93", "sys_exit");
+                asm_code.push_str("    svc #0\n");
+            }
+        }
+    }
+
+    if program.statements.is_empty() {
+        emit(&mut asm_code, options, "mov x8, #93", "sys_exit");
+        emit(&mut asm_code, options, "mov x0, #0", "falling off the end exits with 0");
+        asm_code.push_str("    svc #0\n");
+    }
+
+    if !ctx.strings.is_empty() {
+        asm_code.push_str("\n.section .rodata\n");
+        for (index, string) in ctx.strings.iter() {
+            asm_code.push_str(&format!("ria_str{}: .asciz {}\n", index, super::gas_string(string)));
+        }
+    }
+
+    asm_code
+}
+
+/// Registers for the syscall number and its arguments, in order.
+const SYSCALL_REGISTERS: [&str; MAX_SYSCALL_ARGS] = ["x8", "x0", "x1", "x2", "x3", "x4", "x5"];
+
+/// Evaluates `expr` into `x0`, keeping syscall operands on the stack until
+/// all of them are computed. `sp` must stay 16-byte aligned, so every slot
+/// takes 16 bytes.
+fn emit_expr<'a>(
+    asm_code: &mut String,
+    options: &CodegenOptions,
+    expr: &'a Expr,
+    ctx: &mut Context<'a>,
+) {
+    match expr {
+        Expr::Number { value, .. } => {
+            // `mov` only encodes 16-bit immediates; larger ones come from a literal pool.
+            let load = if (0..=0xffff).contains(value) {
+                format!("mov x0, #{}", value)
+            } else {
+                format!("ldr x0, ={}", value)
+            };
+            emit(asm_code, options, &load, "number");
+        }
+        Expr::Syscall { args, .. } => {
+            for arg in args {
+                emit_expr(asm_code, options, arg, ctx);
+                emit(asm_code, options, "str x0, [sp, #-16]!", "syscall operand");
+            }
+            for register in SYSCALL_REGISTERS[..args.len()].iter().rev() {
+                emit(asm_code, options, &format!("ldr {}, [sp], #16", register), "syscall operand");
+            }
+            asm_code.push_str("    svc #0\n");
+        }
+        Expr::Time { .. } => {
+            emit(asm_code, options, "sub sp, sp, #16", "room for struct timespec");
+            emit(asm_code, options, "mov x8, #113", "sys_clock_gettime");
+            emit(asm_code, options, "mov x0, #0", "CLOCK_REALTIME");
+            emit(asm_code, options, "mov x1, sp", "timespec");
+            asm_code.push_str("    svc #0\n");
+            emit(asm_code, options, "ldr x0, [sp]", "tv_sec");
+            emit(asm_code, options, "add sp, sp, #16", "release timespec");
+        }
+        Expr::Spawn { path, .. } => {
+            let id = ctx.next_label();
+            let string = ctx.strings.intern(path);
+
+            // There is no fork syscall here; clone with just SIGCHLD is the same.
+            emit(asm_code, options, "mov x8, #220", "sys_clone");
+            emit(asm_code, options, "mov x0, #17", "SIGCHLD");
+            emit(asm_code, options, "mov x1, #0", "same stack");
+            emit(asm_code, options, "mov x2, #0", "parent_tid");
+            emit(asm_code, options, "mov x3, #0", "tls");
+            emit(asm_code, options, "mov x4, #0", "child_tid");
+            asm_code.push_str("    svc #0\n");
+            emit(asm_code, options, &format!("cbnz x0, .Lspawn{}_parent", id), "parent");
+            emit(asm_code, options, &format!("adrp x0, ria_str{}", string), "path");
+            emit(asm_code, options, &format!("add x0, x0, :lo12:ria_str{}", string), "path");
+            emit(asm_code, options, "stp x0, xzr, [sp, #-16]!", "argv = { path, NULL }");
+            emit(asm_code, options, "mov x1, sp", "argv");
+            emit(asm_code, options, "mov x2, #0", "empty environment");
+            emit(asm_code, options, "mov x8, #221", "sys_execve");
+            asm_code.push_str("    svc #0\n");
+            emit(asm_code, options, "mov x8, #93", "execve failed: sys_exit");
+            emit(asm_code, options, "mov x0, #127", "command not found");
+            asm_code.push_str("    svc #0\n");
+            asm_code.push_str(&format!(".Lspawn{}_parent:\n", id));
+            emit(asm_code, options, &format!("tbnz x0, #63, .Lspawn{}_done", id), "fork failed");
+            emit(asm_code, options, "sub sp, sp, #16", "room for the wait status");
+            emit(asm_code, options, "mov x1, sp", "status");
+            emit(asm_code, options, "mov x2, #0", "options");
+            emit(asm_code, options, "mov x3, #0", "rusage");
+            emit(asm_code, options, "mov x8, #260", "sys_wait4");
+            asm_code.push_str("    svc #0\n");
+            emit(asm_code, options, "ldr w0, [sp]", "status");
+            emit(asm_code, options, "ubfx x0, x0, #8, #8", "WEXITSTATUS");
+            emit(asm_code, options, "add sp, sp, #16", "release the wait status");
+            asm_code.push_str(&format!(".Lspawn{}_done:\n", id));
+        }
+        Expr::Binary { op, lhs, rhs, .. } => {
+            emit_expr(asm_code, options, lhs, ctx);
+            emit(asm_code, options, "str x0, [sp, #-16]!", "left operand");
+            emit_expr(asm_code, options, rhs, ctx);
+            emit(asm_code, options, "mov x1, x0", "right operand");
+            emit(asm_code, options, "ldr x0, [sp], #16", "left operand");
+            // sdiv gives 0 for a zero divisor, which makes x % 0 == x below.
+            match op {
+                BinOp::Add => emit(asm_code, options, "add x0, x0, x1", "+"),
+                BinOp::Sub => emit(asm_code, options, "sub x0, x0, x1", "-"),
+                BinOp::Mul => emit(asm_code, options, "mul x0, x0, x1", "*"),
+                BinOp::Div => emit(asm_code, options, "sdiv x0, x0, x1", "/"),
+                BinOp::Rem => {
+                    emit(asm_code, options, "sdiv x2, x0, x1", "quotient");
+                    emit(asm_code, options, "msub x0, x2, x1, x0", "x - quotient * y");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+mod aarch64;
+mod riscv64;
+mod x86_64;
+
+use crate::ast::Program;
+use crate::target::{Arch, Target};
+
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    pub target: Target,
+    /// Address memory relative to `rip` so the code can be loaded anywhere.
+    pub pie: bool,
+    /// Leave comments out of the generated assembly.
+    pub compact: bool,
+    /// Export the program as a callable `ria_main` instead of `_start`, so the
+    /// object can be linked into a program that has its own entry point.
+    pub library: bool,
+    /// Count how often each statement runs and write the counters to this
+    /// file right before the program exits.
+    pub coverage: Option<String>,
+}
+
+pub const LIBRARY_ENTRY: &str = "ria_main";
+
+fn emit_line(
+    asm_code: &mut String,
+    options: &CodegenOptions,
+    comment_marker: &str,
+    instruction: &str,
+    comment: &str,
+) {
+    if options.compact {
+        asm_code.push_str(&format!("    {}\n", instruction));
+    } else {
+        asm_code.push_str(&format!("    {:<15} {} {}\n", instruction, comment_marker, comment));
+    }
+}
+
+/// String literals used by a program, each stored once however often it
+/// appears. Backends label entry `i` as `ria_str{i}`.
+#[derive(Debug, Default)]
+pub struct StringPool<'a> {
+    strings: Vec<&'a str>,
+}
+
+impl<'a> StringPool<'a> {
+    /// Returns the index of `s`, adding it the first time it is seen.
+    pub fn intern(&mut self, s: &'a str) -> usize {
+        match self.strings.iter().position(|&existing| existing == s) {
+            Some(index) => index,
+            None => {
+                self.strings.push(s);
+                self.strings.len() - 1
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &'a str)> + '_ {
+        self.strings.iter().copied().enumerate()
+    }
+}
+
+/// State shared by the expressions of one code generation run.
+#[derive(Default)]
+struct Context<'a> {
+    strings: StringPool<'a>,
+    labels: usize,
+}
+
+impl Context<'_> {
+    /// Returns a number no other call in this run returns, for making local
+    /// labels unique.
+    fn next_label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+}
+
+/// Renders `s` NUL-terminated for nasm's `db`: printable runs are quoted and
+/// everything else, including `"`, is written as a byte value.
+fn nasm_string(s: &str) -> String {
+    let mut parts = Vec::new();
+    let mut run = String::new();
+    for byte in s.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'"' {
+            run.push(byte as char);
+            continue;
+        }
+        if !run.is_empty() {
+            parts.push(format!("\"{}\"", std::mem::take(&mut run)));
+        }
+        parts.push(byte.to_string());
+    }
+    if !run.is_empty() {
+        parts.push(format!("\"{}\"", run));
+    }
+    parts.push("0".to_string());
+    parts.join(", ")
+}
+
+/// Renders `s` as the quoted operand of GNU as's `.asciz`, escaping quotes,
+/// backslashes and anything unprintable.
+fn gas_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for byte in s.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            0x20..0x7f => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn generate(program: &Program, options: &CodegenOptions) -> String {
+    match options.target.arch {
+        Arch::X86_64 => x86_64::generate(program, options),
+        Arch::Aarch64 => aarch64::generate(program, options),
+        Arch::Riscv64 => riscv64::generate(program, options),
+    }
+}
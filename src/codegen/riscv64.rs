@@ -0,0 +1,150 @@
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Program, Stmt};
+
+use super::{CodegenOptions, Context, LIBRARY_ENTRY};
+
+fn emit(asm_code: &mut String, options: &CodegenOptions, instruction: &str, comment: &str) {
+    super::emit_line(asm_code, options, "#", instruction, comment);
+}
+
+/// Generates GNU as syntax for RV64 Linux, where the syscall number goes in
+/// `a7`, the first argument in `a0`, and `ecall` traps.
+pub fn generate(program: &Program, options: &CodegenOptions) -> String {
+    let mut asm_code = String::new();
+    let mut ctx = Context::default();
+
+    let entry = if options.library { LIBRARY_ENTRY } else { "_start" };
+    asm_code.push_str(&format!(".global {}\n", entry));
+    asm_code.push_str(".text\n");
+    asm_code.push_str(&format!("{}:\n", entry));
+
+    for stmt in &program.statements {
+        match stmt {
+            Stmt::Return { value: Expr::Number { value, .. }, .. } => {
+                emit(&mut asm_code, options, "li a7, 93", "sys_exit");
+                emit(&mut asm_code, options, &format!("li a0, {}", value), "exit code");
+                asm_code.push_str("    ecall\n");
+            }
+            Stmt::Return { value, .. } => {
+                emit_expr(&mut asm_code, options, value, &mut ctx);
+                emit(&mut asm_code, options, "li a7, 93", "sys_exit");
+                asm_code.push_str("    ecall\n");
+            }
+        }
+    }
+
+    if program.statements.is_empty() {
+        emit(&mut asm_code, options, "li a7, 93", "sys_exit");
+        emit(&mut asm_code, options, "li a0, 0", "falling off the end exits with 0");
+        asm_code.push_str("    ecall\n");
+    }
+
+    if !ctx.strings.is_empty() {
+        asm_code.push_str("\n.section .rodata\n");
+        for (index, string) in ctx.strings.iter() {
+            asm_code.push_str(&format!("ria_str{}: .asciz {}\n", index, super::gas_string(string)));
+        }
+    }
+
+    asm_code
+}
+
+/// Registers for the syscall number and its arguments, in order.
+const SYSCALL_REGISTERS: [&str; MAX_SYSCALL_ARGS] = ["a7", "a0", "a1", "a2", "a3", "a4", "a5"];
+
+/// Evaluates `expr` into `a0`, keeping syscall operands on the stack until
+/// all of them are computed. The psABI keeps `sp` 16-byte aligned, so every
+/// slot takes 16 bytes.
+fn emit_expr<'a>(
+    asm_code: &mut String,
+    options: &CodegenOptions,
+    expr: &'a Expr,
+    ctx: &mut Context<'a>,
+) {
+    match expr {
+        Expr::Number { value, .. } => {
+            emit(asm_code, options, &format!("li a0, {}", value), "number");
+        }
+        Expr::Syscall { args, .. } => {
+            for arg in args {
+                emit_expr(asm_code, options, arg, ctx);
+                emit(asm_code, options, "addi sp, sp, -16", "make room");
+                emit(asm_code, options, "sd a0, 0(sp)", "syscall operand");
+            }
+            for register in SYSCALL_REGISTERS[..args.len()].iter().rev() {
+                emit(asm_code, options, &format!("ld {}, 0(sp)", register), "syscall operand");
+                emit(asm_code, options, "addi sp, sp, 16", "release slot");
+            }
+            asm_code.push_str("    ecall\n");
+        }
+        Expr::Time { .. } => {
+            emit(asm_code, options, "addi sp, sp, -16", "room for struct timespec");
+            emit(asm_code, options, "li a7, 113", "sys_clock_gettime");
+            emit(asm_code, options, "li a0, 0", "CLOCK_REALTIME");
+            emit(asm_code, options, "mv a1, sp", "timespec");
+            asm_code.push_str("    ecall\n");
+            emit(asm_code, options, "ld a0, 0(sp)", "tv_sec");
+            emit(asm_code, options, "addi sp, sp, 16", "release timespec");
+        }
+        Expr::Spawn { path, .. } => {
+            let id = ctx.next_label();
+            let string = ctx.strings.intern(path);
+
+            // There is no fork syscall here; clone with just SIGCHLD is the same.
+            emit(asm_code, options, "li a7, 220", "sys_clone");
+            emit(asm_code, options, "li a0, 17", "SIGCHLD");
+            emit(asm_code, options, "li a1, 0", "same stack");
+            emit(asm_code, options, "li a2, 0", "parent_tid");
+            emit(asm_code, options, "li a3, 0", "tls");
+            emit(asm_code, options, "li a4, 0", "child_tid");
+            asm_code.push_str("    ecall\n");
+            emit(asm_code, options, &format!("bnez a0, .Lspawn{}_parent", id), "parent");
+            emit(asm_code, options, &format!("la a0, ria_str{}", string), "path");
+            emit(asm_code, options, "addi sp, sp, -16", "room for argv");
+            emit(asm_code, options, "sd a0, 0(sp)", "argv[0] = path");
+            emit(asm_code, options, "sd zero, 8(sp)", "argv[1] = NULL");
+            emit(asm_code, options, "mv a1, sp", "argv");
+            emit(asm_code, options, "li a2, 0", "empty environment");
+            emit(asm_code, options, "li a7, 221", "sys_execve");
+            asm_code.push_str("    ecall\n");
+            emit(asm_code, options, "li a7, 93", "execve failed: sys_exit");
+            emit(asm_code, options, "li a0, 127", "command not found");
+            asm_code.push_str("    ecall\n");
+            asm_code.push_str(&format!(".Lspawn{}_parent:\n", id));
+            emit(asm_code, options, &format!("bltz a0, .Lspawn{}_done", id), "fork failed");
+            emit(asm_code, options, "addi sp, sp, -16", "room for the wait status");
+            emit(asm_code, options, "mv a1, sp", "status");
+            emit(asm_code, options, "li a2, 0", "options");
+            emit(asm_code, options, "li a3, 0", "rusage");
+            emit(asm_code, options, "li a7, 260", "sys_wait4");
+            asm_code.push_str("    ecall\n");
+            emit(asm_code, options, "lw a0, 0(sp)", "status");
+            emit(asm_code, options, "srli a0, a0, 8", "WEXITSTATUS");
+            emit(asm_code, options, "andi a0, a0, 255", "low byte");
+            emit(asm_code, options, "addi sp, sp, 16", "release the wait status");
+            asm_code.push_str(&format!(".Lspawn{}_done:\n", id));
+        }
+        Expr::Binary { op, lhs, rhs, .. } => {
+            emit_expr(asm_code, options, lhs, ctx);
+            emit(asm_code, options, "addi sp, sp, -16", "make room");
+            emit(asm_code, options, "sd a0, 0(sp)", "left operand");
+            emit_expr(asm_code, options, rhs, ctx);
+            emit(asm_code, options, "mv a1, a0", "right operand");
+            emit(asm_code, options, "ld a0, 0(sp)", "left operand");
+            emit(asm_code, options, "addi sp, sp, 16", "release slot");
+            match op {
+                BinOp::Add => emit(asm_code, options, "add a0, a0, a1", "+"),
+                BinOp::Sub => emit(asm_code, options, "sub a0, a0, a1", "-"),
+                BinOp::Mul => emit(asm_code, options, "mul a0, a0, a1", "*"),
+                BinOp::Div => {
+                    // div gives -1 for a zero divisor; mask it to the 0 the
+                    // other targets give.
+                    emit(asm_code, options, "div a0, a0, a1", "/");
+                    emit(asm_code, options, "snez t0, a1", "divisor != 0");
+                    emit(asm_code, options, "neg t0, t0", "all ones unless dividing by zero");
+                    emit(asm_code, options, "and a0, a0, t0", "x / 0 is 0");
+                }
+                BinOp::Rem => emit(asm_code, options, "rem a0, a0, a1", "% (x % 0 is x)"),
+            }
+        }
+    }
+}
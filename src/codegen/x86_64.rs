@@ -0,0 +1,213 @@
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Program, Stmt};
+
+use super::{CodegenOptions, Context, LIBRARY_ENTRY};
+
+fn emit(asm_code: &mut String, options: &CodegenOptions, instruction: &str, comment: &str) {
+    super::emit_line(asm_code, options, ";", instruction, comment);
+}
+
+pub fn generate(program: &Program, options: &CodegenOptions) -> String {
+    let mut asm_code = String::new();
+    let mut ctx = Context::default();
+
+    if options.pie {
+        asm_code.push_str("default rel\n");
+    }
+    let entry = if options.library { LIBRARY_ENTRY } else { "_start" };
+    asm_code.push_str(&format!("global {}\n", entry));
+    asm_code.push_str("section .text\n");
+    asm_code.push_str(&format!("{}:\n", entry));
+
+    for (index, stmt) in program.statements.iter().enumerate() {
+        if options.coverage.is_some() {
+            let counter = format!("inc qword [rel ria_cov_counters + {}]", index * 8);
+            emit(&mut asm_code, options, &counter, "coverage counter");
+        }
+
+        match stmt {
+            Stmt::Return { value: Expr::Number { value, .. }, .. } => {
+                if options.coverage.is_some() {
+                    emit(&mut asm_code, options, "call ria_cov_dump", "write coverage counters");
+                }
+                emit(&mut asm_code, options, "mov rax, 60", "sys_exit");
+                emit(&mut asm_code, options, &format!("mov rdi, {}", value), "exit code");
+                asm_code.push_str("    syscall\n");
+            }
+            Stmt::Return { value, .. } => {
+                emit_expr(&mut asm_code, options, value, &mut ctx);
+                if options.coverage.is_some() {
+                    emit(&mut asm_code, options, "push rax", "save exit code");
+                    emit(&mut asm_code, options, "call ria_cov_dump", "write coverage counters");
+                    emit(&mut asm_code, options, "pop rax", "restore exit code");
+                }
+                emit(&mut asm_code, options, "mov rdi, rax", "exit code");
+                emit(&mut asm_code, options, "mov rax, 60", "sys_exit");
+                asm_code.push_str("    syscall\n");
+            }
+        }
+    }
+
+    if program.statements.is_empty() {
+        if options.coverage.is_some() {
+            emit(&mut asm_code, options, "call ria_cov_dump", "write coverage counters");
+        }
+        emit(&mut asm_code, options, "mov rax, 60", "sys_exit");
+        emit(&mut asm_code, options, "xor edi, edi", "falling off the end exits with 0");
+        asm_code.push_str("    syscall\n");
+    }
+
+    if options.coverage.is_some() {
+        emit_coverage_runtime(&mut asm_code, options, program.statements.len());
+    }
+
+    if !ctx.strings.is_empty() || options.coverage.is_some() {
+        asm_code.push_str("\nsection .rodata\n");
+        if let Some(path) = &options.coverage {
+            asm_code.push_str(&format!("ria_cov_path: db {}\n", super::nasm_string(path)));
+        }
+        for (index, string) in ctx.strings.iter() {
+            asm_code.push_str(&format!("ria_str{}: db {}\n", index, super::nasm_string(string)));
+        }
+    }
+
+    asm_code
+}
+
+/// Registers for the syscall number and its arguments, in order.
+const SYSCALL_REGISTERS: [&str; MAX_SYSCALL_ARGS] = ["rax", "rdi", "rsi", "rdx", "r10", "r8", "r9"];
+
+/// Evaluates `expr` into `rax`. Syscall operands are evaluated left to right
+/// onto the stack and then popped into their registers, so nested syscalls
+/// cannot clobber arguments that were already computed.
+fn emit_expr<'a>(
+    asm_code: &mut String,
+    options: &CodegenOptions,
+    expr: &'a Expr,
+    ctx: &mut Context<'a>,
+) {
+    match expr {
+        Expr::Number { value, .. } => {
+            emit(asm_code, options, &format!("mov rax, {}", value), "number");
+        }
+        Expr::Syscall { args, .. } => {
+            for arg in args {
+                emit_expr(asm_code, options, arg, ctx);
+                emit(asm_code, options, "push rax", "syscall operand");
+            }
+            for register in SYSCALL_REGISTERS[..args.len()].iter().rev() {
+                emit(asm_code, options, &format!("pop {}", register), "syscall operand");
+            }
+            asm_code.push_str("    syscall\n");
+        }
+        Expr::Time { .. } => {
+            emit(asm_code, options, "sub rsp, 16", "room for struct timespec");
+            emit(asm_code, options, "mov rax, 228", "sys_clock_gettime");
+            emit(asm_code, options, "mov rdi, 0", "CLOCK_REALTIME");
+            emit(asm_code, options, "mov rsi, rsp", "timespec");
+            asm_code.push_str("    syscall\n");
+            emit(asm_code, options, "mov rax, [rsp]", "tv_sec");
+            emit(asm_code, options, "add rsp, 16", "release timespec");
+        }
+        Expr::Spawn { path, .. } => {
+            let id = ctx.next_label();
+            let string = ctx.strings.intern(path);
+
+            emit(asm_code, options, "mov rax, 57", "sys_fork");
+            asm_code.push_str("    syscall\n");
+            emit(asm_code, options, "test rax, rax", "parent or child?");
+            emit(asm_code, options, &format!("jnz .spawn{}_parent", id), "parent");
+            emit(asm_code, options, &format!("lea rdi, [rel ria_str{}]", string), "path");
+            emit(asm_code, options, "push 0", "argv[1] = NULL");
+            emit(asm_code, options, "push rdi", "argv[0] = path");
+            emit(asm_code, options, "mov rsi, rsp", "argv");
+            emit(asm_code, options, "mov rdx, 0", "empty environment");
+            emit(asm_code, options, "mov rax, 59", "sys_execve");
+            asm_code.push_str("    syscall\n");
+            emit(asm_code, options, "mov rax, 60", "execve failed: sys_exit");
+            emit(asm_code, options, "mov rdi, 127", "command not found");
+            asm_code.push_str("    syscall\n");
+            asm_code.push_str(&format!(".spawn{}_parent:\n", id));
+            emit(asm_code, options, &format!("js .spawn{}_done", id), "fork failed");
+            emit(asm_code, options, "sub rsp, 16", "room for the wait status");
+            emit(asm_code, options, "mov rdi, rax", "child pid");
+            emit(asm_code, options, "mov rax, 61", "sys_wait4");
+            emit(asm_code, options, "mov rsi, rsp", "status");
+            emit(asm_code, options, "mov rdx, 0", "options");
+            emit(asm_code, options, "mov r10, 0", "rusage");
+            asm_code.push_str("    syscall\n");
+            emit(asm_code, options, "mov eax, [rsp]", "status");
+            emit(asm_code, options, "shr eax, 8", "WEXITSTATUS");
+            emit(asm_code, options, "and eax, 255", "low byte");
+            emit(asm_code, options, "add rsp, 16", "release the wait status");
+            asm_code.push_str(&format!(".spawn{}_done:\n", id));
+        }
+        Expr::Binary { op, lhs, rhs, .. } => {
+            emit_expr(asm_code, options, lhs, ctx);
+            emit(asm_code, options, "push rax", "left operand");
+            emit_expr(asm_code, options, rhs, ctx);
+            emit(asm_code, options, "mov rcx, rax", "right operand");
+            emit(asm_code, options, "pop rax", "left operand");
+            match op {
+                BinOp::Add => emit(asm_code, options, "add rax, rcx", "+"),
+                BinOp::Sub => emit(asm_code, options, "sub rax, rcx", "-"),
+                BinOp::Mul => emit(asm_code, options, "imul rax, rcx", "*"),
+                BinOp::Div | BinOp::Rem => {
+                    // idiv faults on a zero divisor and on i64::MIN / -1, so
+                    // skip it for both and keep the results the other targets
+                    // give: x / 0 == 0, x % 0 == x, x / -1 == -x (wrapping)
+                    // and x % -1 == 0.
+                    let id = ctx.next_label();
+                    if *op == BinOp::Div {
+                        emit(asm_code, options, "xor edx, edx", "x / 0 is 0");
+                    } else {
+                        emit(asm_code, options, "mov rdx, rax", "x % 0 is x");
+                    }
+                    emit(asm_code, options, "test rcx, rcx", "dividing by zero?");
+                    emit(asm_code, options, &format!("jz .div{}_done", id), "then skip idiv");
+                    if *op == BinOp::Div {
+                        emit(asm_code, options, "mov rdx, rax", "x / -1 is -x");
+                        emit(asm_code, options, "neg rdx", "wrapping at i64::MIN");
+                    } else {
+                        emit(asm_code, options, "xor edx, edx", "x % -1 is 0");
+                    }
+                    emit(asm_code, options, "cmp rcx, -1", "dividing by -1?");
+                    emit(asm_code, options, &format!("je .div{}_done", id), "then skip idiv");
+                    emit(asm_code, options, "cqo", "sign-extend into rdx");
+                    emit(asm_code, options, "idiv rcx", "rax = quotient, rdx = remainder");
+                    if *op == BinOp::Div {
+                        emit(asm_code, options, "mov rdx, rax", "quotient");
+                    }
+                    asm_code.push_str(&format!(".div{}_done:\n", id));
+                    emit(asm_code, options, "mov rax, rdx", "result");
+                }
+            }
+        }
+    }
+}
+
+/// Emits the counter table and `ria_cov_dump`, which writes the counters to
+/// `ria_cov_path` as little-endian 64-bit integers, one per statement.
+fn emit_coverage_runtime(asm_code: &mut String, options: &CodegenOptions, counters: usize) {
+    asm_code.push_str("\nria_cov_dump:\n");
+    emit(asm_code, options, "mov rax, 2", "sys_open");
+    emit(asm_code, options, "lea rdi, [rel ria_cov_path]", "path");
+    emit(asm_code, options, "mov rsi, 577", "O_WRONLY | O_CREAT | O_TRUNC");
+    emit(asm_code, options, "mov rdx, 420", "mode 0644");
+    asm_code.push_str("    syscall\n");
+    emit(asm_code, options, "test rax, rax", "open failed?");
+    emit(asm_code, options, "js .done", "then skip the dump");
+    emit(asm_code, options, "mov rdi, rax", "fd");
+    emit(asm_code, options, "mov rax, 1", "sys_write");
+    emit(asm_code, options, "lea rsi, [rel ria_cov_counters]", "buffer");
+    emit(asm_code, options, &format!("mov rdx, {}", counters * 8), "length");
+    asm_code.push_str("    syscall\n");
+    emit(asm_code, options, "mov rax, 3", "sys_close");
+    asm_code.push_str("    syscall\n");
+    asm_code.push_str(".done:\n");
+    asm_code.push_str("    ret\n");
+
+    // The counters start at zero, so they only need space reserved in .bss
+    // rather than zero bytes stored in the binary.
+    asm_code.push_str("\nsection .bss\n");
+    asm_code.push_str(&format!("ria_cov_counters: resq {}\n", counters));
+}
@@ -0,0 +1,348 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::ast::Program;
+use crate::bytecode;
+use crate::cfg::Cfg;
+use crate::cli::{Emit, OptLevel};
+use crate::codegen::{self, CodegenOptions};
+use crate::conditional::{self, Conditions};
+use crate::diagnostics::Diagnostic;
+use crate::include;
+use crate::lexer::{self, Span, Token};
+use crate::limits::Limits;
+use crate::llvm;
+use crate::macros;
+use crate::parser::Parser;
+use crate::passes::{Pass, PassManager};
+use crate::peephole;
+use crate::target::{Arch, Target};
+use crate::timing::PassTimer;
+
+/// What a [`Compiler`] produced, selected with [`Compiler::emit`].
+#[derive(Debug, Clone)]
+pub enum Artifact {
+    Tokens(Vec<Token>),
+    Ast(Program),
+    /// The control-flow graph in Graphviz dot syntax.
+    Cfg(String),
+    LlvmIr(String),
+    /// An encoded `.riac` file.
+    Bytecode(Vec<u8>),
+    Assembly(String),
+}
+
+/// What [`Compiler::generate`] makes from a parsed program. Tokens are not
+/// among them, since they come from [`Compiler::lex`] before parsing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Output {
+    Ast,
+    Cfg,
+    LlvmIr,
+    Bytecode,
+    Assembly,
+}
+
+impl Output {
+    /// The output for `--emit` kind `emit`, assembly without one, or `None`
+    /// for tokens.
+    pub fn of(emit: Option<Emit>) -> Option<Self> {
+        match emit {
+            Some(Emit::Tokens) => None,
+            Some(Emit::Ast) => Some(Output::Ast),
+            Some(Emit::Cfg) => Some(Output::Cfg),
+            Some(Emit::LlvmIr) => Some(Output::LlvmIr),
+            Some(Emit::Bytecode) => Some(Output::Bytecode),
+            None => Some(Output::Assembly),
+        }
+    }
+}
+
+/// Why [`Compiler::compile`] failed, by the stage that rejected the
+/// program. Each carries the diagnostic the command line would print.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompileError {
+    /// The source is too large, an included file is missing or cyclic, or a
+    /// conditional section is malformed.
+    Preprocess(Diagnostic),
+    /// The source could not be split into tokens.
+    Lex(Diagnostic),
+    /// A macro definition or use is malformed, or expanding it went past a
+    /// limit.
+    Macro(Diagnostic),
+    Parse(Diagnostic),
+    /// A custom pass rejected the program.
+    Pass(Diagnostic),
+}
+
+impl CompileError {
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            CompileError::Preprocess(diagnostic)
+            | CompileError::Lex(diagnostic)
+            | CompileError::Macro(diagnostic)
+            | CompileError::Parse(diagnostic)
+            | CompileError::Pass(diagnostic) => diagnostic,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.diagnostic().code
+    }
+
+    /// Where in the source the error is, if it is about one place.
+    pub fn span(&self) -> Option<Span> {
+        self.diagnostic().span
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.diagnostic().fmt(f)
+    }
+}
+
+impl Error for CompileError {}
+
+impl From<CompileError> for Diagnostic {
+    fn from(error: CompileError) -> Self {
+        match error {
+            CompileError::Preprocess(diagnostic)
+            | CompileError::Lex(diagnostic)
+            | CompileError::Macro(diagnostic)
+            | CompileError::Parse(diagnostic)
+            | CompileError::Pass(diagnostic) => diagnostic,
+        }
+    }
+}
+
+/// Runs the compiler in-process, without the native toolchain and, unless
+/// [`path`](Compiler::path) is set, without touching the file system:
+///
+/// ```
+/// use ria::{Artifact, Compiler};
+///
+/// let artifact = Compiler::new().source("kharrej 0;").compile().unwrap();
+/// assert!(matches!(artifact, Artifact::Assembly(_)));
+/// ```
+#[derive(Debug, Default)]
+pub struct Compiler {
+    source: String,
+    name: String,
+    path: Option<PathBuf>,
+    include_paths: Vec<String>,
+    cfg_flags: Vec<String>,
+    target: Target,
+    opt_level: OptLevel,
+    optimize_size: bool,
+    pie: bool,
+    library: bool,
+    coverage: Option<String>,
+    emit: Option<Emit>,
+    passes: PassManager,
+    limits: Limits,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            name: "main.ria".to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// File name recorded in outputs that mention their source, such as the
+    /// LLVM module ID.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The file the source was read from. Setting it lets `jme3` include
+    /// files next to it and from the [`include_paths`](Compiler::include_paths).
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn include_paths(mut self, include_paths: Vec<String>) -> Self {
+        self.include_paths = include_paths;
+        self
+    }
+
+    /// Sets a name `#ila` sections can test, like `--cfg NAME` or
+    /// `--cfg NAME=value`.
+    pub fn cfg(mut self, flag: impl Into<String>) -> Self {
+        self.cfg_flags.push(flag.into());
+        self
+    }
+
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    pub fn optimize_size(mut self, optimize_size: bool) -> Self {
+        self.optimize_size = optimize_size;
+        self
+    }
+
+    /// Generate position-independent code.
+    pub fn pie(mut self, pie: bool) -> Self {
+        self.pie = pie;
+        self
+    }
+
+    /// Export the program as `ria_main` instead of `_start`.
+    pub fn library(mut self, library: bool) -> Self {
+        self.library = library;
+        self
+    }
+
+    /// Write statement counters to `file` when the program exits.
+    pub fn coverage(mut self, file: Option<String>) -> Self {
+        self.coverage = file;
+        self
+    }
+
+    /// Stop after producing `emit` instead of generating assembly.
+    pub fn emit(mut self, emit: Emit) -> Self {
+        self.emit = Some(emit);
+        self
+    }
+
+    /// Caps on the source size, token count and nesting depth the compiler
+    /// accepts before giving up with E0019.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Registers a custom pass at the end of the pipeline.
+    pub fn pass(mut self, pass: impl Pass + 'static) -> Self {
+        self.passes.add(pass);
+        self
+    }
+
+    /// Replaces the custom pass pipeline, for callers that need to control
+    /// ordering or switch passes off.
+    pub fn passes(mut self, passes: PassManager) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    pub fn compile(&self) -> Result<Artifact, CompileError> {
+        let mut timer = PassTimer::new(false);
+        let source = self.preprocess(&mut timer)?;
+        let tokens = self.lex(&source, &mut timer)?;
+        let Some(output) = Output::of(self.emit) else {
+            return Ok(Artifact::Tokens(tokens));
+        };
+        let program = self.parse(tokens, &source, &mut timer)?;
+        Ok(self.generate(program, output, &mut timer))
+    }
+
+    /// The first stage of [`compile`](Self::compile): evaluates the `#ila`
+    /// sections, resolves includes when a path is set, and checks the
+    /// result against the size limit.
+    pub fn preprocess(&self, timer: &mut PassTimer) -> Result<String, CompileError> {
+        let conditions = Conditions::new(&self.target, &self.cfg_flags);
+        let (source, what) = match &self.path {
+            Some(path) => {
+                let expanded = timer.time("preprocessing", || {
+                    let include_paths = &self.include_paths;
+                    include::expand(&self.source, path, include_paths, &conditions, &self.limits)
+                });
+                (expanded, format!("'{}' with its includes", self.name))
+            }
+            None => {
+                let evaluated = timer
+                    .time("preprocessing", || conditional::evaluate(&self.source, &conditions));
+                (evaluated, format!("'{}'", self.name))
+            }
+        };
+        let source = source.map_err(CompileError::Preprocess)?;
+        self.limits
+            .check_file_size(&what, source.len() as u64)
+            .map_err(CompileError::Preprocess)?;
+        Ok(source)
+    }
+
+    /// Splits the preprocessed `source` into tokens.
+    pub fn lex(&self, source: &str, timer: &mut PassTimer) -> Result<Vec<Token>, CompileError> {
+        timer
+            .time("lexing", || lexer::tokenize(source, self.limits.tokens))
+            .map_err(CompileError::Lex)
+    }
+
+    /// Expands macros in the `tokens` of `source`, parses them and runs the
+    /// custom passes over the tree.
+    pub fn parse(
+        &self,
+        tokens: Vec<Token>,
+        source: &str,
+        timer: &mut PassTimer,
+    ) -> Result<Program, CompileError> {
+        let tokens = timer
+            .time("macro expansion", || macros::expand(tokens, source, &self.limits))
+            .map_err(CompileError::Macro)?;
+        let mut program = timer
+            .time("parsing", || Parser::new(tokens).max_depth(self.limits.depth).parse_program())
+            .map_err(CompileError::Parse)?;
+        if !self.passes.is_empty() {
+            timer
+                .time("plugin checks", || self.passes.run_ast(&mut program))
+                .map_err(CompileError::Pass)?;
+        }
+        Ok(program)
+    }
+
+    /// Produces `output` from a parsed program.
+    pub fn generate(&self, program: Program, output: Output, timer: &mut PassTimer) -> Artifact {
+        match output {
+            Output::Ast => Artifact::Ast(program),
+            Output::Cfg => Artifact::Cfg(Cfg::build(&program).to_dot()),
+            Output::LlvmIr => {
+                Artifact::LlvmIr(llvm::emit_module(&Cfg::build(&program), &self.name, &self.target))
+            }
+            Output::Bytecode => {
+                let code = timer.time("bytecode", || bytecode::compile(&program));
+                Artifact::Bytecode(bytecode::encode(&code))
+            }
+            Output::Assembly => Artifact::Assembly(self.assemble(&program, timer)),
+        }
+    }
+
+    /// Generates assembly for `program`, then runs the peephole optimizer
+    /// when optimizing and the custom passes over the text.
+    pub fn assemble(&self, program: &Program, timer: &mut PassTimer) -> String {
+        let options = CodegenOptions {
+            target: self.target,
+            pie: self.pie,
+            compact: self.optimize_size,
+            library: self.library,
+            coverage: self.coverage.clone(),
+        };
+        let mut asm_code = timer.time("codegen", || codegen::generate(program, &options));
+
+        let optimize = self.opt_level >= OptLevel::O1 || self.optimize_size;
+        if optimize && self.target.arch == Arch::X86_64 {
+            asm_code = timer.time("peephole", || peephole::optimize(&asm_code));
+        }
+        if !self.passes.is_empty() {
+            asm_code = timer.time("plugin passes", || self.passes.run_asm(asm_code));
+        }
+        asm_code
+    }
+}
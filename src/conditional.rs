@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use crate::diagnostics::{self, Diagnostic};
+use crate::target::Target;
+
+/// The names `#ila` conditions can test: `TARGET` (the operating system,
+/// e.g. `"linux"`), `ARCH` (e.g. `"x86_64"`) and everything passed with
+/// `--cfg NAME` or `--cfg NAME=value`.
+#[derive(Debug, Clone, Default)]
+pub struct Conditions {
+    values: HashMap<String, String>,
+}
+
+impl Conditions {
+    pub fn new(target: &Target, flags: &[String]) -> Self {
+        let mut values = HashMap::new();
+        values.insert("TARGET".to_string(), target.os.name().to_string());
+        values.insert("ARCH".to_string(), target.arch.name().to_string());
+        for flag in flags {
+            let (name, value) = flag.split_once('=').unwrap_or((flag, ""));
+            values.insert(name.to_string(), value.to_string());
+        }
+        Conditions { values }
+    }
+
+    /// Evaluates `NAME`, `!NAME`, `NAME == "value"` or `NAME != "value"`.
+    fn eval(&self, condition: &str) -> Result<bool, String> {
+        let condition = condition.trim();
+
+        for (operator, equal) in [("==", true), ("!=", false)] {
+            if let Some((name, value)) = condition.split_once(operator) {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .ok_or_else(|| format!("Expected a quoted value in '{}'", condition))?;
+                let matches = self.values.get(name.trim()).is_some_and(|v| v == value);
+                return Ok(matches == equal);
+            }
+        }
+
+        let (negated, name) = match condition.strip_prefix('!') {
+            Some(name) => (true, name.trim()),
+            None => (false, condition),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("Invalid condition '{}'", condition));
+        }
+        Ok(self.values.contains_key(name) != negated)
+    }
+}
+
+struct Section {
+    /// Whether the enclosing sections are all active.
+    parent_active: bool,
+    taken: bool,
+    in_else: bool,
+}
+
+fn error(line: usize, message: impl Into<String>) -> Diagnostic {
+    Diagnostic::new(
+        diagnostics::E_CONDITIONAL,
+        format!("line {}: {}", line + 1, message.into()),
+    )
+}
+
+/// Keeps the lines of `#ila COND` ... `#wla` ... `#sala` sections whose
+/// condition holds and blanks out the rest. Directive lines are blanked too,
+/// so line numbers in later diagnostics and coverage reports still match.
+pub fn evaluate(source: &str, conditions: &Conditions) -> Result<String, Diagnostic> {
+    let mut out = String::with_capacity(source.len());
+    let mut sections: Vec<Section> = Vec::new();
+    let active = |sections: &[Section]| {
+        sections.last().is_none_or(|s| s.parent_active && (s.taken != s.in_else))
+    };
+
+    for (number, line) in source.split_inclusive('\n').enumerate() {
+        let directive = line.trim();
+
+        if let Some(condition) = directive.strip_prefix("#ila") {
+            let parent_active = active(&sections);
+            let taken = conditions.eval(condition).map_err(|e| error(number, e))?;
+            sections.push(Section { parent_active, taken, in_else: false });
+        } else if directive == "#wla" {
+            match sections.last_mut() {
+                Some(section) if !section.in_else => section.in_else = true,
+                Some(_) => return Err(error(number, "'#wla' appears twice in one section")),
+                None => return Err(error(number, "'#wla' without a matching '#ila'")),
+            }
+        } else if directive == "#sala" {
+            if sections.pop().is_none() {
+                return Err(error(number, "'#sala' without a matching '#ila'"));
+            }
+        } else if active(&sections) {
+            out.push_str(line);
+            continue;
+        }
+
+        if line.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    if !sections.is_empty() {
+        return Err(Diagnostic::new(
+            diagnostics::E_CONDITIONAL,
+            "'#ila' section is never closed with '#sala'",
+        ));
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,75 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::project::{self, MANIFEST};
+
+/// `[build]` settings that stand for a flag taking the setting's value.
+const VALUE_FLAGS: &[(&str, &str)] = &[
+    ("target", "--target"),
+    ("crate-type", "--crate-type"),
+    ("link", "--link"),
+];
+
+/// The user's configuration: `$XDG_CONFIG_HOME/ria/config.toml`, falling
+/// back to `~/.config/ria/config.toml`.
+pub fn user_config() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("ria").join("config.toml"))
+}
+
+/// Turns the `[build]` section of `text`, read from `file`, into the
+/// command-line flags it stands for. `opt-level` is one of "0", "1", "2"
+/// and "s":
+///
+/// ```toml
+/// [build]
+/// target = "aarch64-unknown-linux-gnu"
+/// opt-level = "2"
+/// crate-type = "bin"
+/// link = "static"
+/// ```
+pub fn parse(text: &str, file: &str) -> Result<Vec<String>, String> {
+    let mut flags = Vec::new();
+
+    project::parse_entries(text, file, |section, key, value| {
+        if section != "build" {
+            return Ok(());
+        }
+        let value = project::quoted(value).ok_or("expected a quoted string")?;
+        if key == "opt-level" {
+            flags.push(match value.as_str() {
+                "0" | "1" | "2" => format!("-O{}", value),
+                "s" => "-Os".to_string(),
+                _ => return Err(format!("unknown opt-level '{}'", value)),
+            });
+            return Ok(());
+        }
+        let (_, flag) = VALUE_FLAGS
+            .iter()
+            .find(|(setting, _)| *setting == key)
+            .ok_or_else(|| format!("unknown build setting '{}'", key))?;
+        flags.push(flag.to_string());
+        flags.push(value.to_string());
+        Ok(())
+    })?;
+
+    Ok(flags)
+}
+
+/// The default flags for building in `root`: the user's settings followed
+/// by the project's `ria.toml`, so the project's win. Flags given on the
+/// command line go after both and win over either.
+pub fn default_flags(root: &Path) -> Result<Vec<String>, String> {
+    let mut flags = Vec::new();
+    for path in user_config().into_iter().chain([root.join(MANIFEST)]) {
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        flags.extend(parse(&text, &path.display().to_string())?);
+    }
+    Ok(flags)
+}
@@ -0,0 +1,47 @@
+use std::fmt::Write;
+
+use crate::ast::{Program, Stmt};
+
+pub fn counter_file(output_name: &str) -> String {
+    format!("{}.cov", output_name)
+}
+
+/// Decodes the counter dump written by an instrumented program.
+pub fn read_counters(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+fn statement_start(stmt: &Stmt) -> usize {
+    match stmt {
+        Stmt::Return { span, .. } => span.start,
+    }
+}
+
+/// Lays the counters over the source, gcov style: each line is prefixed with
+/// how many times its statements ran, `#####` for statements that never ran,
+/// and `-` for lines without a statement.
+pub fn report(source: &str, program: &Program, counters: &[u64]) -> String {
+    let line_count = source.lines().count();
+    let mut line_counts: Vec<Option<u64>> = vec![None; line_count];
+
+    for (stmt, count) in program.statements.iter().zip(counters) {
+        let line = source[..statement_start(stmt)].matches('\n').count();
+        if let Some(slot) = line_counts.get_mut(line) {
+            *slot = Some(slot.unwrap_or(0) + count);
+        }
+    }
+
+    let mut out = String::new();
+    for (number, (text, count)) in source.lines().zip(line_counts).enumerate() {
+        let count = match count {
+            Some(0) => "#####".to_string(),
+            Some(count) => count.to_string(),
+            None => "-".to_string(),
+        };
+        let _ = writeln!(out, "{:>9}: {:>4}: {}", count, number + 1, text);
+    }
+    out
+}
@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::project::{self, Manifest};
+
+/// Records the commit each git dependency resolved to, next to the manifest.
+pub const LOCKFILE: &str = "ria.lock";
+
+/// Where dependencies are checked out, relative to the project root.
+const CACHE_DIR: &str = ".ria/git";
+
+/// A `ria.lock` entry: the dependency as the manifest asked for it and the
+/// commit that request resolved to.
+#[derive(Debug, Clone, PartialEq)]
+struct Locked {
+    name: String,
+    git: String,
+    rev: Option<String>,
+    commit: String,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Error running git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn read_lockfile(root: &Path) -> Result<Vec<Locked>, String> {
+    let Ok(text) = fs::read_to_string(root.join(LOCKFILE)) else {
+        return Ok(Vec::new());
+    };
+
+    let mut locked = Vec::new();
+    project::parse_entries(&text, LOCKFILE, |section, name, value| {
+        if section != "dependencies" {
+            return Ok(());
+        }
+        let fields = project::inline_table(value).ok_or("expected an inline table")?;
+        let field = |key| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        let entry = Locked {
+            name: name.to_string(),
+            git: field("git").ok_or("missing 'git'")?,
+            rev: field("rev"),
+            commit: field("commit").ok_or("missing 'commit'")?,
+        };
+        project::check_git_argument("git", &entry.git)?;
+        project::check_git_argument("commit", &entry.commit)?;
+        if let Some(rev) = &entry.rev {
+            project::check_git_argument("rev", rev)?;
+        }
+        locked.push(entry);
+        Ok(())
+    })?;
+    Ok(locked)
+}
+
+fn render_lockfile(locked: &[Locked]) -> String {
+    let mut text = String::new();
+    text.push_str("# Written by zakaria. Commit this file to pin dependency versions.\n");
+    text.push_str("[dependencies]\n");
+    for entry in locked {
+        let rev = entry
+            .rev
+            .as_ref()
+            .map(|rev| format!(", rev = {}", project::escape(rev)))
+            .unwrap_or_default();
+        text.push_str(&format!(
+            "{} = {{ git = {}{}, commit = {} }}\n",
+            entry.name,
+            project::escape(&entry.git),
+            rev,
+            project::escape(&entry.commit)
+        ));
+    }
+    text
+}
+
+/// Checks out `candidates[0]`, or the next candidate if that fails.
+fn checkout(dir: &Path, candidates: &[String]) -> Result<(), String> {
+    let mut error = String::new();
+    for candidate in candidates {
+        // checkout does not take --end-of-options; the trailing "--" keeps
+        // the candidate from being read as a path, and values starting with
+        // '-' are rejected when the manifest and lockfile are read.
+        match git(dir, &["checkout", "--quiet", "--detach", candidate, "--"]) {
+            Ok(_) => return Ok(()),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// Fetches the git dependencies of the project at `root` into its cache and
+/// returns their checkouts, which go on the `jme3` search path.
+///
+/// A dependency recorded in `ria.lock` is checked out at the recorded commit
+/// for as long as its manifest entry is unchanged; anything else resolves
+/// `rev` (a branch, tag or commit, or the default branch when unset) against
+/// the remote and records the result.
+pub fn resolve(root: &Path, manifest: &Manifest) -> Result<Vec<String>, String> {
+    let previous = read_lockfile(root)?;
+    let mut locked = Vec::new();
+    let mut paths = Vec::new();
+
+    for dependency in &manifest.dependencies {
+        let dir = root.join(CACHE_DIR).join(&dependency.name);
+        let cloned = !dir.join(".git").exists();
+        if cloned {
+            // stderr, so --emit output on stdout stays machine-readable.
+            eprintln!("Fetching {} from {}...", dependency.name, dependency.git);
+            fs::create_dir_all(root.join(CACHE_DIR))
+                .map_err(|e| format!("Error creating '{}': {}", CACHE_DIR, e))?;
+            let dir = dir.display().to_string();
+            git(root, &["clone", "--quiet", "--", &dependency.git, &dir])?;
+        }
+
+        let pinned = previous.iter().find(|entry| {
+            entry.name == dependency.name
+                && entry.git == dependency.git
+                && entry.rev == dependency.rev
+        });
+        let candidates = match (pinned, &dependency.rev) {
+            (Some(entry), _) => vec![entry.commit.clone()],
+            // Prefer the remote branch so a stale local one is never used.
+            (None, Some(rev)) => vec![format!("origin/{}", rev), rev.clone()],
+            (None, None) => vec!["origin/HEAD".to_string()],
+        };
+        // Without a pin the dependency follows the remote, so bring an
+        // existing clone up to date first.
+        if pinned.is_none() && !cloned {
+            git(&dir, &["fetch", "--quiet", "--tags", "origin"])?;
+        }
+        if checkout(&dir, &candidates).is_err() {
+            // A pinned commit can be newer than the cached clone.
+            git(&dir, &["fetch", "--quiet", "--tags", "origin"])?;
+            checkout(&dir, &candidates)
+                .map_err(|e| format!("Cannot check out dependency '{}': {}", dependency.name, e))?;
+        }
+
+        locked.push(Locked {
+            name: dependency.name.clone(),
+            git: dependency.git.clone(),
+            rev: dependency.rev.clone(),
+            commit: git(&dir, &["rev-parse", "HEAD"])?,
+        });
+        paths.push(dir.display().to_string());
+    }
+
+    if locked != previous {
+        let path = root.join(LOCKFILE);
+        fs::write(&path, render_lockfile(&locked))
+            .map_err(|e| format!("Error writing '{}': {}", path.display(), e))?;
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,437 @@
+use std::fmt;
+
+use crate::lexer::Span;
+
+pub const E_MISSING_SEMI: &str = "E0001";
+pub const E_EXPECTED_NUMBER: &str = "E0002";
+pub const E_INCOMPLETE_RETURN: &str = "E0003";
+pub const E_EXIT_CODE_RANGE: &str = "E0004";
+pub const E_INVALID_NUMBER: &str = "E0005";
+pub const E_NO_RETURN: &str = "E0006";
+pub const E_UNEXPECTED_CHAR: &str = "E0007";
+pub const E_PLUGIN: &str = "E0008";
+pub const E_MACRO: &str = "E0009";
+pub const E_INCLUDE: &str = "E0010";
+pub const E_CONDITIONAL: &str = "E0011";
+pub const E_SYSCALL: &str = "E0012";
+pub const E_BUILTIN_ARGS: &str = "E0013";
+pub const E_UNTERMINATED_STRING: &str = "E0014";
+pub const E_EXPECTED_OPERAND: &str = "E0015";
+pub const E_UNCLOSED_PAREN: &str = "E0016";
+pub const E_UNKNOWN_KEYWORD: &str = "E0017";
+pub const E_INVALID_UTF8: &str = "E0018";
+pub const E_LIMIT: &str = "E0019";
+pub const E_UNEXPECTED_TOKEN: &str = "E0020";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    /// The source the diagnostic is about, when it is about one place.
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            code,
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub fn at(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error[{}]: {}", self.code, self.message)
+    }
+}
+
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        E_MISSING_SEMI,
+        "A semicolon is missing after the exit code.
+
+Every statement in Ria ends with `;`. The compiler found a number after
+`kharrej` but the next token was not a semicolon. (When the file simply
+ends after the number, the error is E0003 instead.)
+
+Erroneous example:
+
+    kharrej 0
+    kharrej 1;
+
+Fixed example:
+
+    kharrej 0;
+    kharrej 1;
+",
+    ),
+    (
+        E_EXPECTED_NUMBER,
+        "`kharrej` must be followed by a number.
+
+`kharrej` exits the program, and the number after it becomes the exit
+code that the shell sees in `$?`. An expression such as `2 + 3` or a
+builtin call such as `syscall(...)` or `waqt()` may stand in for the
+number, in which case its result is the exit code.
+
+Erroneous example:
+
+    kharrej code;
+
+Fixed example:
+
+    kharrej 0;
+",
+    ),
+    (
+        E_INCOMPLETE_RETURN,
+        "The file ended in the middle of a `kharrej` statement.
+
+A complete statement has three parts: the keyword, a number and a
+semicolon.
+
+Erroneous example:
+
+    kharrej 1
+
+Fixed example:
+
+    kharrej 1;
+",
+    ),
+    (
+        E_EXIT_CODE_RANGE,
+        "The exit code is outside the range 0 to 255.
+
+Linux keeps only the lowest 8 bits of a process exit status, so Ria
+rejects exit codes that would silently wrap around.
+
+Erroneous example:
+
+    kharrej 256;
+
+Fixed example:
+
+    kharrej 255;
+",
+    ),
+    (
+        E_INVALID_NUMBER,
+        "A number literal is too large.
+
+Ria reads number literals as 32-bit signed integers. Since literals have
+no sign, a literal must lie between 0 and 2147483647. The message gives the literal and the byte
+where it starts.
+
+Erroneous example:
+
+    kharrej 99999999999;
+
+Fixed example:
+
+    kharrej 9;
+",
+    ),
+    (
+        E_NO_RETURN,
+        "The program has no `kharrej` statement.
+
+Note: this error is no longer emitted. A program without `kharrej` now
+falls off the end and exits with 0, so an empty file is a valid program.
+To exit with a different code, end the program with a `kharrej`:
+
+    kharrej 3;
+",
+    ),
+    (
+        E_UNEXPECTED_CHAR,
+        "The lexer found a character that is not part of the Ria language.
+
+Only letters, digits, `_`, `;`, `(`, `)`, `,`, the operators `+`, `-`,
+`*`, `/` and `%`, string literals and whitespace may appear in a Ria
+source file.
+
+Erroneous example:
+
+    kharrej 4$;
+
+Fixed example:
+
+    kharrej 4;
+",
+    ),
+    (
+        E_PLUGIN,
+        "A compiler plugin rejected the program.
+
+Plugins loaded with `--plugin` can check each `kharrej` statement and
+refuse programs that break their rules, for example a course lint that
+only allows certain exit codes. The message names the plugin that
+reported the problem; see its documentation for what it expects.
+",
+    ),
+    (
+        E_MACRO,
+        "A `makro` definition or use is malformed.
+
+A macro is defined on a single line as `makro NAME(a, b) body`, where
+the body runs to the end of the line. Every use must come after the
+definition and pass exactly as many arguments as there are parameters.
+A macro cannot be defined twice.
+
+Erroneous example:
+
+    makro EXIT(code) kharrej code;
+    EXIT(1, 2)
+
+Fixed example:
+
+    makro EXIT(code) kharrej code;
+    EXIT(1)
+",
+    ),
+    (
+        E_INCLUDE,
+        "A `jme3` include could not be resolved.
+
+`jme3 \"file.ria\";` on a line of its own is replaced with the contents
+of that file. The file is looked up next to the including file first and
+then in every directory passed with `-I`. A file cannot include itself,
+directly or through other files.
+
+Erroneous example:
+
+    // a.ria
+    jme3 \"a.ria\";
+
+Fixed example:
+
+    // a.ria
+    jme3 \"defs.ria\";
+",
+    ),
+    (
+        E_CONDITIONAL,
+        "A conditional compilation section is malformed.
+
+Sections start with `#ila CONDITION`, may have one `#wla` (else) and
+end with `#sala`, each on a line of its own. A condition is `NAME`,
+`!NAME`, `NAME == \"value\"` or `NAME != \"value\"`, where `TARGET` is
+the operating system, `ARCH` is the architecture and other names come
+from `--cfg`.
+
+Erroneous example:
+
+    #ila TARGET == linux
+    kharrej 0;
+
+Fixed example:
+
+    #ila TARGET == \"linux\"
+    kharrej 0;
+    #sala
+",
+    ),
+    (
+        E_SYSCALL,
+        "A `syscall(...)` expression is malformed.
+
+`syscall` takes the syscall number followed by up to six arguments,
+separated by commas and wrapped in parentheses. Each argument is a number
+or another `syscall(...)`. The numbers are the ones of the target's Linux
+ABI, so `#ila ARCH == \"...\"` sections help when a program should build
+for several architectures.
+
+Erroneous example:
+
+    kharrej syscall 39;
+
+Fixed example:
+
+    kharrej syscall(39);
+",
+    ),
+    (
+        E_BUILTIN_ARGS,
+        "A builtin was called with the wrong arguments.
+
+`waqt()` reads the current time and takes no arguments. The empty
+parentheses are still required, so that it reads as a call.
+`chghel(\"/path/to/program\")` takes exactly one string: the program to
+run.
+
+Erroneous example:
+
+    kharrej waqt;
+
+Fixed example:
+
+    kharrej waqt();
+",
+    ),
+    (
+        E_UNTERMINATED_STRING,
+        "A string literal is missing its closing quote.
+
+Strings start and end with `\"` on the same line.
+
+Erroneous example:
+
+    kharrej chghel(\"/bin/true);
+
+Fixed example:
+
+    kharrej chghel(\"/bin/true\");
+",
+    ),
+    (
+        E_EXPECTED_OPERAND,
+        "An operator is missing its right-hand operand.
+
+The operators `+`, `-`, `*`, `/` and `%` each need a number, a builtin
+call or a parenthesized expression on both sides.
+
+Erroneous example:
+
+    kharrej 2 + ;
+
+Fixed example:
+
+    kharrej 2 + 3;
+",
+    ),
+    (
+        E_UNCLOSED_PAREN,
+        "A `(` is never closed.
+
+Parentheses group part of an expression so it is computed first. Every
+`(` needs a matching `)` before the statement ends; the message gives
+the byte offset of the `(` that is left open.
+
+Erroneous example:
+
+    kharrej (2 + 3 * 4;
+
+Fixed example:
+
+    kharrej (2 + 3) * 4;
+",
+    ),
+    (
+        E_UNKNOWN_KEYWORD,
+        "A word is a misspelling of a keyword or builtin.
+
+Where a statement starts, the compiler expects `kharrej` or `makro`; where
+an operand starts, it expects a number or one of the builtins `syscall`,
+`waqt` and `chghel`. The word it found is close to one of these, which the
+message suggests.
+
+Erroneous example:
+
+    kharej waqtt();
+
+Fixed example:
+
+    kharrej waqt();
+",
+    ),
+    (
+        E_INVALID_UTF8,
+        "A source file is not valid UTF-8.
+
+Ria source files, including those pulled in with `jme3`, must be saved
+as UTF-8. A leading byte order mark is accepted and ignored. The message
+gives the first byte that does not decode and its offset in the file.
+This usually means the file was saved in a legacy encoding such as
+Windows-1252 or Latin-1.
+
+Re-save the file as UTF-8 in your editor, or convert it:
+
+    iconv -f WINDOWS-1252 -t UTF-8 program.ria > fixed.ria
+",
+    ),
+    (
+        E_LIMIT,
+        "The program is larger or nests deeper than the compiler allows.
+
+To keep generated or hostile input from exhausting memory or the stack,
+the compiler stops with this error when the source, together with its
+`jme3` includes, is larger than 16 MiB, when it has more than 1,048,576
+tokens as written or after macro expansion, or when an expression or a
+macro use nests more than 256 levels deep. Nesting counts parentheses,
+builtin arguments and operators; a long chain such as `1 + 2 + 3 + ...`
+nests one level per operator because it groups as `((1 + 2) + 3) + ...`.
+A macro use nests one level deeper for each macro whose body or
+arguments it appears in.
+
+The message names the limit that was hit. Raise it with `--limit`:
+
+    zakaria build program.ria --limit depth=1000
+
+The limits are `file-size` in bytes, `tokens` and `depth`.
+",
+    ),
+    (
+        E_UNEXPECTED_TOKEN,
+        "A statement starts with something other than `kharrej`.
+
+Every statement in Ria starts with `kharrej`; `makro` definitions are
+removed before statements are read. The compiler found another word, a
+number or a symbol where the next statement should begin. When the word
+is close to a keyword, the error is E0017 instead, which suggests it.
+
+Erroneous example:
+
+    hello kharrej 3;
+
+Fixed example:
+
+    kharrej 3;
+",
+    ),
+];
+
+/// The number of single-character insertions, deletions and substitutions
+/// that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Returns the candidate `name` is most likely a misspelling of, if any is
+/// close enough: within one edit for every three characters of `name`.
+pub fn closest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, text)| *text)
+}
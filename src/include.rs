@@ -0,0 +1,139 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::conditional::{self, Conditions};
+use crate::diagnostics::{self, Diagnostic};
+use crate::limits::{self, Limits};
+use crate::source;
+
+/// Returns the file named by a `jme3 "file.ria";` line, if `line` is one.
+fn directive(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix("jme3")?;
+    let quoted = rest.trim_start().strip_suffix(';')?.trim_end();
+    quoted.strip_prefix('"')?.strip_suffix('"')
+}
+
+fn error(message: impl Into<String>) -> Diagnostic {
+    Diagnostic::new(diagnostics::E_INCLUDE, message)
+}
+
+/// Looks for `name` next to the including file first, then in each include
+/// path in order.
+fn locate(name: &str, including: &Path, include_paths: &[String]) -> Option<PathBuf> {
+    let dir = including.parent().unwrap_or(Path::new(""));
+    std::iter::once(dir.to_path_buf())
+        .chain(include_paths.iter().map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// State shared by the files of one expansion.
+struct Expansion<'a> {
+    include_paths: &'a [String],
+    conditions: &'a Conditions,
+    limits: &'a Limits,
+    /// The file the expansion started from, for the size limit message.
+    root: &'a Path,
+    /// Files currently being included, outermost first.
+    stack: Vec<PathBuf>,
+    /// Bytes of expanded text produced so far, across all files.
+    expanded: u64,
+}
+
+impl Expansion<'_> {
+    /// Counts `bytes` more of output, failing as soon as the total passes the
+    /// size limit so that repeated includes cannot exhaust memory first.
+    fn produce(&mut self, bytes: usize) -> Result<(), Diagnostic> {
+        self.expanded += bytes as u64;
+        if self.expanded <= self.limits.file_size as u64 {
+            return Ok(());
+        }
+        Err(limits::exceeded(
+            format!(
+                "'{}' with its includes is more than the limit of {} bytes",
+                self.root.display(),
+                self.limits.file_size
+            ),
+            "file-size",
+        ))
+    }
+}
+
+/// Replaces every `jme3 "file.ria";` line in `source` with the contents of
+/// that file, recursively. A file may be included more than once, but not
+/// while it is already being included, since that would never terminate.
+///
+/// Each file's `#ila` sections are evaluated before its includes are
+/// resolved, so an inactive section can name a file that does not exist.
+/// Included files and the expanded text are both held to `limits.file_size`.
+pub fn expand(
+    source: &str,
+    path: &Path,
+    include_paths: &[String],
+    conditions: &Conditions,
+    limits: &Limits,
+) -> Result<String, Diagnostic> {
+    let mut expansion = Expansion {
+        include_paths,
+        conditions,
+        limits,
+        root: path,
+        stack: vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())],
+        expanded: 0,
+    };
+    expand_file(source, path, &mut expansion)
+}
+
+fn expand_file(
+    source: &str,
+    path: &Path,
+    expansion: &mut Expansion,
+) -> Result<String, Diagnostic> {
+    let source = conditional::evaluate(source, expansion.conditions).map_err(|e| Diagnostic {
+        message: format!("'{}' {}", path.display(), e.message),
+        ..e
+    })?;
+    let mut out = String::with_capacity(source.len());
+
+    for line in source.split_inclusive('\n') {
+        let Some(name) = directive(line) else {
+            expansion.produce(line.len())?;
+            out.push_str(line);
+            continue;
+        };
+
+        let included = locate(name, path, expansion.include_paths)
+            .ok_or_else(|| error(format!("Cannot find included file '{}'", name)))?;
+        let canonical = included.canonicalize().unwrap_or_else(|_| included.clone());
+
+        if expansion.stack.contains(&canonical) {
+            let chain: Vec<String> = expansion
+                .stack
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(error(format!("Include cycle: {}", chain.join(" -> "))));
+        }
+
+        let what = format!("'{}'", included.display());
+        let read_error = |e: io::Error| error(format!("Error reading {}: {}", what, e));
+        // Checked before reading so that an enormous file is never loaded.
+        let size = fs::metadata(&included).map_err(read_error)?.len();
+        expansion.limits.check_file_size(&what, size)?;
+        let bytes = fs::read(&included).map_err(read_error)?;
+        let content = source::decode(bytes, &included.display().to_string())?;
+
+        expansion.stack.push(canonical);
+        out.push_str(&expand_file(&content, &included, expansion)?);
+        expansion.stack.pop();
+
+        if !out.ends_with('\n') {
+            expansion.produce(1)?;
+            out.push('\n');
+        }
+    }
+
+    Ok(out)
+}
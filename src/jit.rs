@@ -0,0 +1,224 @@
+use std::ffi::c_void;
+use std::ptr;
+
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Program, Stmt};
+use crate::codegen::StringPool;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const PROT_EXEC: i32 = 0x4;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+unsafe extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut c_void;
+    fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> i32;
+    fn munmap(addr: *mut c_void, len: usize) -> i32;
+}
+
+/// `pop` encodings for the syscall number and argument registers, in the
+/// order `rax, rdi, rsi, rdx, r10, r8, r9`.
+const POP_SYSCALL_REGISTERS: [&[u8]; MAX_SYSCALL_ARGS] =
+    [&[0x58], &[0x5f], &[0x5e], &[0x5a], &[0x41, 0x5a], &[0x41, 0x58], &[0x41, 0x59]];
+
+/// Points the `rel32` operand at `at` to `target`, both offsets into `code`.
+fn patch_rel32(code: &mut [u8], at: usize, target: usize) {
+    let rel = target as i64 - (at as i64 + 4);
+    code[at..at + 4].copy_from_slice(&(rel as i32).to_le_bytes());
+}
+
+/// Encodes the program as an x86-64 function returning the exit code in
+/// `eax`: every `kharrej` becomes `mov eax, imm32; ret`, or evaluates its
+/// expression into `rax` first. Each distinct string literal is appended once after the code.
+pub fn encode(program: &Program) -> Vec<u8> {
+    let mut code = Vec::new();
+    let mut strings = Vec::new();
+
+    for stmt in &program.statements {
+        match stmt {
+            Stmt::Return { value: Expr::Number { value, .. }, .. } => {
+                code.push(0xb8);
+                code.extend_from_slice(&value.to_le_bytes());
+                code.push(0xc3);
+            }
+            Stmt::Return { value, .. } => {
+                encode_expr(value, &mut code, &mut strings);
+                code.push(0xc3);
+            }
+        }
+    }
+    if program.statements.is_empty() {
+        // Falling off the end exits with 0: xor eax, eax; ret.
+        code.extend_from_slice(&[0x31, 0xc0, 0xc3]);
+    }
+
+    // Lay each distinct string out once; every use of it points at that copy.
+    let mut pool = StringPool::default();
+    let fixups: Vec<(usize, usize)> =
+        strings.into_iter().map(|(at, string)| (at, pool.intern(string))).collect();
+    let mut offsets = Vec::new();
+    for (_, string) in pool.iter() {
+        offsets.push(code.len());
+        code.extend_from_slice(string.as_bytes());
+        code.push(0);
+    }
+    for (at, index) in fixups {
+        patch_rel32(&mut code, at, offsets[index]);
+    }
+
+    code
+}
+
+/// Mirrors the assembly backend: operands go through the stack so nested
+/// syscalls cannot clobber registers that are already loaded.
+fn encode_expr<'a>(expr: &'a Expr, code: &mut Vec<u8>, strings: &mut Vec<(usize, &'a str)>) {
+    match expr {
+        Expr::Number { value, .. } => {
+            // mov rax, imm32 (sign-extended)
+            code.extend_from_slice(&[0x48, 0xc7, 0xc0]);
+            code.extend_from_slice(&value.to_le_bytes());
+        }
+        Expr::Syscall { args, .. } => {
+            for arg in args {
+                encode_expr(arg, code, strings);
+                code.push(0x50); // push rax
+            }
+            for pop in POP_SYSCALL_REGISTERS[..args.len()].iter().rev() {
+                code.extend_from_slice(pop);
+            }
+            code.extend_from_slice(&[0x0f, 0x05]); // syscall
+        }
+        Expr::Time { .. } => {
+            code.extend_from_slice(&[0x48, 0x83, 0xec, 0x10]); // sub rsp, 16
+            code.extend_from_slice(&[0xb8, 0xe4, 0x00, 0x00, 0x00]); // mov eax, 228
+            code.extend_from_slice(&[0x31, 0xff]); // xor edi, edi
+            code.extend_from_slice(&[0x48, 0x89, 0xe6]); // mov rsi, rsp
+            code.extend_from_slice(&[0x0f, 0x05]); // syscall
+            code.extend_from_slice(&[0x48, 0x8b, 0x04, 0x24]); // mov rax, [rsp]
+            code.extend_from_slice(&[0x48, 0x83, 0xc4, 0x10]); // add rsp, 16
+        }
+        Expr::Spawn { path, .. } => {
+            code.extend_from_slice(&[0xb8, 0x39, 0x00, 0x00, 0x00]); // mov eax, 57 (fork)
+            code.extend_from_slice(&[0x0f, 0x05]); // syscall
+            code.extend_from_slice(&[0x48, 0x85, 0xc0]); // test rax, rax
+            code.extend_from_slice(&[0x0f, 0x85]); // jnz parent
+            let to_parent = code.len();
+            code.extend_from_slice(&[0; 4]);
+
+            code.extend_from_slice(&[0x48, 0x8d, 0x3d]); // lea rdi, [rip + path]
+            strings.push((code.len(), path));
+            code.extend_from_slice(&[0; 4]);
+            code.extend_from_slice(&[0x6a, 0x00]); // push 0
+            code.push(0x57); // push rdi
+            code.extend_from_slice(&[0x48, 0x89, 0xe6]); // mov rsi, rsp
+            code.extend_from_slice(&[0x31, 0xd2]); // xor edx, edx
+            code.extend_from_slice(&[0xb8, 0x3b, 0x00, 0x00, 0x00]); // mov eax, 59 (execve)
+            code.extend_from_slice(&[0x0f, 0x05]); // syscall
+            code.extend_from_slice(&[0xb8, 0x3c, 0x00, 0x00, 0x00]); // mov eax, 60 (exit)
+            code.extend_from_slice(&[0xbf, 0x7f, 0x00, 0x00, 0x00]); // mov edi, 127
+            code.extend_from_slice(&[0x0f, 0x05]); // syscall
+
+            let parent = code.len();
+            patch_rel32(code, to_parent, parent);
+            code.extend_from_slice(&[0x0f, 0x88]); // js done
+            let to_done = code.len();
+            code.extend_from_slice(&[0; 4]);
+            code.extend_from_slice(&[0x48, 0x83, 0xec, 0x10]); // sub rsp, 16
+            code.extend_from_slice(&[0x48, 0x89, 0xc7]); // mov rdi, rax
+            code.extend_from_slice(&[0xb8, 0x3d, 0x00, 0x00, 0x00]); // mov eax, 61 (wait4)
+            code.extend_from_slice(&[0x48, 0x89, 0xe6]); // mov rsi, rsp
+            code.extend_from_slice(&[0x31, 0xd2]); // xor edx, edx
+            code.extend_from_slice(&[0x45, 0x31, 0xd2]); // xor r10d, r10d
+            code.extend_from_slice(&[0x0f, 0x05]); // syscall
+            code.extend_from_slice(&[0x8b, 0x04, 0x24]); // mov eax, [rsp]
+            code.extend_from_slice(&[0xc1, 0xe8, 0x08]); // shr eax, 8
+            code.extend_from_slice(&[0x25, 0xff, 0x00, 0x00, 0x00]); // and eax, 255
+            code.extend_from_slice(&[0x48, 0x83, 0xc4, 0x10]); // add rsp, 16
+
+            let done = code.len();
+            patch_rel32(code, to_done, done);
+        }
+        Expr::Binary { op, lhs, rhs, .. } => {
+            encode_expr(lhs, code, strings);
+            code.push(0x50); // push rax
+            encode_expr(rhs, code, strings);
+            code.extend_from_slice(&[0x48, 0x89, 0xc1]); // mov rcx, rax
+            code.push(0x58); // pop rax
+            match op {
+                BinOp::Add => code.extend_from_slice(&[0x48, 0x01, 0xc8]), // add rax, rcx
+                BinOp::Sub => code.extend_from_slice(&[0x48, 0x29, 0xc8]), // sub rax, rcx
+                BinOp::Mul => code.extend_from_slice(&[0x48, 0x0f, 0xaf, 0xc1]), // imul rax, rcx
+                BinOp::Div | BinOp::Rem => {
+                    if *op == BinOp::Div {
+                        code.extend_from_slice(&[0x31, 0xd2]); // xor edx, edx (x / 0 is 0)
+                    } else {
+                        code.extend_from_slice(&[0x48, 0x89, 0xc2]); // mov rdx, rax (x % 0 is x)
+                    }
+                    code.extend_from_slice(&[0x48, 0x85, 0xc9]); // test rcx, rcx
+                    code.extend_from_slice(&[0x0f, 0x84]); // jz done
+                    let zero_to_done = code.len();
+                    code.extend_from_slice(&[0; 4]);
+                    // idiv also faults on i64::MIN / -1.
+                    if *op == BinOp::Div {
+                        code.extend_from_slice(&[0x48, 0x89, 0xc2]); // mov rdx, rax
+                        code.extend_from_slice(&[0x48, 0xf7, 0xda]); // neg rdx (x / -1 is -x)
+                    } else {
+                        code.extend_from_slice(&[0x31, 0xd2]); // xor edx, edx (x % -1 is 0)
+                    }
+                    code.extend_from_slice(&[0x48, 0x83, 0xf9, 0xff]); // cmp rcx, -1
+                    code.extend_from_slice(&[0x0f, 0x84]); // je done
+                    let minus_one_to_done = code.len();
+                    code.extend_from_slice(&[0; 4]);
+                    code.extend_from_slice(&[0x48, 0x99]); // cqo
+                    code.extend_from_slice(&[0x48, 0xf7, 0xf9]); // idiv rcx
+                    if *op == BinOp::Div {
+                        code.extend_from_slice(&[0x48, 0x89, 0xc2]); // mov rdx, rax
+                    }
+
+                    let done = code.len();
+                    patch_rel32(code, zero_to_done, done);
+                    patch_rel32(code, minus_one_to_done, done);
+                    code.extend_from_slice(&[0x48, 0x89, 0xd0]); // mov rax, rdx
+                }
+            }
+        }
+    }
+}
+
+/// Copies `code` into a fresh page, flips it from writable to executable and
+/// calls it, returning the program's exit code.
+pub fn run(code: &[u8]) -> Result<i32, String> {
+    if !cfg!(target_arch = "x86_64") {
+        return Err("--jit is only supported on x86_64 hosts".to_string());
+    }
+
+    let len = code.len().max(1);
+
+    unsafe {
+        let memory = mmap(
+            ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        if memory as isize == -1 {
+            return Err("mmap failed".to_string());
+        }
+
+        ptr::copy_nonoverlapping(code.as_ptr(), memory as *mut u8, code.len());
+
+        if mprotect(memory, len, PROT_READ | PROT_EXEC) != 0 {
+            munmap(memory, len);
+            return Err("mprotect failed".to_string());
+        }
+
+        let entry: extern "C" fn() -> i32 = std::mem::transmute(memory);
+        let exit_code = entry();
+
+        munmap(memory, len);
+        // Like a real exit status, only the low byte survives.
+        Ok(exit_code & 0xff)
+    }
+}
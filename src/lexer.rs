@@ -0,0 +1,251 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::diagnostics::{self, Diagnostic};
+use crate::limits;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenType {
+    Return,
+    Makro,
+    Ident,
+    Number,
+    Str,
+    Semi,
+    LParen,
+    RParen,
+    Comma,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+}
+
+impl TokenType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::Return => "return",
+            TokenType::Makro => "makro",
+            TokenType::Ident => "ident",
+            TokenType::Number => "number",
+            TokenType::Str => "string",
+            TokenType::Semi => "semi",
+            TokenType::LParen => "lparen",
+            TokenType::RParen => "rparen",
+            TokenType::Comma => "comma",
+            TokenType::Plus => "plus",
+            TokenType::Minus => "minus",
+            TokenType::Star => "star",
+            TokenType::Slash => "slash",
+            TokenType::Percent => "percent",
+        }
+    }
+}
+
+/// Byte range of a token in the source text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub value: Option<String>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    UnexpectedChar { ch: char, offset: usize },
+    UnterminatedString { offset: usize },
+    /// A number literal too large for the `i32` literals are read as.
+    NumberOutOfRange { literal: String, offset: usize },
+}
+
+impl LexError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedChar { .. } => diagnostics::E_UNEXPECTED_CHAR,
+            LexError::UnterminatedString { .. } => diagnostics::E_UNTERMINATED_STRING,
+            LexError::NumberOutOfRange { .. } => diagnostics::E_INVALID_NUMBER,
+        }
+    }
+
+    /// The source the error is about: the character, the opening quote or
+    /// the literal.
+    pub fn span(&self) -> Span {
+        let (start, len) = match self {
+            LexError::UnexpectedChar { ch, offset } => (*offset, ch.len_utf8()),
+            LexError::UnterminatedString { offset } => (*offset, 1),
+            LexError::NumberOutOfRange { literal, offset } => (*offset, literal.len()),
+        };
+        Span { start, end: start + len }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::UnexpectedChar { ch, offset } => {
+                write!(f, "Unexpected character '{}' at byte {}", ch, offset)
+            }
+            LexError::UnterminatedString { offset } => {
+                write!(f, "Unterminated string starting at byte {}", offset)
+            }
+            LexError::NumberOutOfRange { literal, offset } => write!(
+                f,
+                "Integer literal '{}' at byte {} is out of range for i32: \
+                 the allowed range is 0 to {}",
+                literal,
+                offset,
+                i32::MAX
+            ),
+        }
+    }
+}
+
+impl From<LexError> for Diagnostic {
+    fn from(e: LexError) -> Self {
+        Diagnostic::new(e.code(), e.to_string()).at(e.span())
+    }
+}
+
+/// Lexes all of `input`, stopping with E0019 as soon as there are more than
+/// `max_tokens` tokens rather than after collecting them all.
+pub fn tokenize(input: &str, max_tokens: usize) -> Result<Vec<Token>, Diagnostic> {
+    let mut tokens = Vec::new();
+    for token in Lexer::new(input) {
+        if tokens.len() == max_tokens {
+            return Err(limits::exceeded(
+                format!("The program has more than {} tokens", max_tokens),
+                "tokens",
+            ));
+        }
+        tokens.push(token?);
+    }
+    Ok(tokens)
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.input.len(), |&(offset, _)| offset)
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&(offset, c)) = self.chars.peek() {
+            match c {
+                'a'..='z' | 'A'..='Z' | '_' => {
+                    let mut identifier = String::new();
+                    while let Some(&(_, ch)) = self.chars.peek() {
+                        if ch.is_alphanumeric() || ch == '_' {
+                            identifier.push(ch);
+                            self.chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+
+                    let (token_type, value) = match identifier.as_str() {
+                        "kharrej" => (TokenType::Return, None),
+                        "makro" => (TokenType::Makro, None),
+                        _ => (TokenType::Ident, Some(identifier)),
+                    };
+
+                    return Some(Ok(Token {
+                        token_type,
+                        value,
+                        span: Span { start: offset, end: self.offset() },
+                    }));
+                }
+                '0'..='9' => {
+                    let mut number = String::new();
+                    while let Some(&(_, ch)) = self.chars.peek() {
+                        if ch.is_ascii_digit() {
+                            number.push(ch);
+                            self.chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    // Only digits were taken, so parsing can only fail on overflow.
+                    if number.parse::<i32>().is_err() {
+                        return Some(Err(LexError::NumberOutOfRange { literal: number, offset }));
+                    }
+
+                    return Some(Ok(Token {
+                        token_type: TokenType::Number,
+                        value: Some(number),
+                        span: Span { start: offset, end: self.offset() },
+                    }));
+                }
+                '"' => {
+                    self.chars.next();
+                    let mut text = String::new();
+                    loop {
+                        match self.chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, '\n')) | None => {
+                                return Some(Err(LexError::UnterminatedString { offset }));
+                            }
+                            Some((_, ch)) => text.push(ch),
+                        }
+                    }
+
+                    return Some(Ok(Token {
+                        token_type: TokenType::Str,
+                        value: Some(text),
+                        span: Span { start: offset, end: self.offset() },
+                    }));
+                }
+                ';' | '(' | ')' | ',' | '+' | '-' | '*' | '/' | '%' => {
+                    self.chars.next();
+                    let token_type = match c {
+                        ';' => TokenType::Semi,
+                        '(' => TokenType::LParen,
+                        ')' => TokenType::RParen,
+                        ',' => TokenType::Comma,
+                        '+' => TokenType::Plus,
+                        '-' => TokenType::Minus,
+                        '*' => TokenType::Star,
+                        '/' => TokenType::Slash,
+                        _ => TokenType::Percent,
+                    };
+                    return Some(Ok(Token {
+                        token_type,
+                        value: None,
+                        span: Span { start: offset, end: offset + 1 },
+                    }));
+                }
+                ' ' | '\t' | '\n' | '\r' => {
+                    self.chars.next();
+                }
+                _ => {
+                    self.chars.next();
+                    return Some(Err(LexError::UnexpectedChar { ch: c, offset }));
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,33 @@
+pub mod ast;
+pub mod bytecode;
+pub mod cfg;
+pub mod cli;
+pub mod codegen;
+pub mod compiler;
+pub mod config;
+pub mod conditional;
+pub mod coverage;
+pub mod deps;
+pub mod diagnostics;
+pub mod include;
+pub mod jit;
+pub mod json;
+pub mod lexer;
+pub mod limits;
+pub mod llvm;
+pub mod macros;
+pub mod parser;
+pub mod passes;
+pub mod plan;
+pub mod plugin;
+pub mod peephole;
+pub mod project;
+pub mod runner;
+pub mod source;
+pub mod stats;
+pub mod target;
+pub mod telemetry;
+pub mod timing;
+pub mod toolchain;
+
+pub use compiler::{Artifact, CompileError, Compiler, Output};
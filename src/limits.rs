@@ -0,0 +1,65 @@
+use crate::diagnostics::{self, Diagnostic};
+
+/// Caps on how much input the compiler takes on, so that generated or
+/// hostile programs fail with E0019 instead of exhausting memory or
+/// overflowing the stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Bytes of source, counting what `jme3` includes pull in.
+    pub file_size: usize,
+    /// Tokens in the program, both as lexed and after macro expansion.
+    pub tokens: usize,
+    /// How deeply an expression may nest through parentheses, builtin
+    /// arguments and operators, and macro uses through other macros.
+    pub depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { file_size: 16 << 20, tokens: 1 << 20, depth: 256 }
+    }
+}
+
+impl Limits {
+    /// Applies one `--limit name=value` setting.
+    pub fn set(&mut self, setting: &str) -> Result<(), String> {
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Expected '--limit <name>=<value>', got '{}'", setting))?;
+        let limit = match name {
+            "file-size" => &mut self.file_size,
+            "tokens" => &mut self.tokens,
+            "depth" => &mut self.depth,
+            _ => {
+                return Err(format!(
+                    "Unknown limit '{}': expected file-size, tokens or depth",
+                    name
+                ));
+            }
+        };
+        *limit = match value.parse() {
+            Ok(value) if value > 0 => value,
+            _ => return Err(format!("Invalid limit '{}': expected a positive number", value)),
+        };
+        Ok(())
+    }
+
+    /// Checks that `bytes` of source named `what` fit in `file_size`.
+    pub fn check_file_size(&self, what: &str, bytes: u64) -> Result<(), Diagnostic> {
+        if bytes <= self.file_size as u64 {
+            return Ok(());
+        }
+        Err(exceeded(
+            format!("{} is {} bytes, more than the limit of {}", what, bytes, self.file_size),
+            "file-size",
+        ))
+    }
+}
+
+/// Reports that the limit named `setting` was hit, as described by `what`.
+pub(crate) fn exceeded(what: String, setting: &str) -> Diagnostic {
+    Diagnostic::new(
+        diagnostics::E_LIMIT,
+        format!("{}; raise it with --limit {}=<n>", what, setting),
+    )
+}
@@ -0,0 +1,245 @@
+use std::fmt::Write;
+
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Stmt};
+use crate::cfg::Cfg;
+use crate::codegen::StringPool;
+use crate::target::{Arch, Target};
+
+/// Renders the program as a textual LLVM module whose `main` returns the exit
+/// code, so it can be compiled with `llc`/`clang` and compared against the
+/// assembly the native backends produce.
+pub fn emit_module(cfg: &Cfg, source_name: &str, target: &Target) -> String {
+    let mut out = String::new();
+    let source_name = escape_bytes(source_name);
+    let _ = writeln!(out, "; ModuleID = '{}'", source_name);
+    let _ = writeln!(out, "source_filename = \"{}\"", source_name);
+    let _ = writeln!(out, "target triple = \"{}\"", target);
+    out.push('\n');
+    out.push_str("define i32 @main() {\n");
+
+    if cfg.blocks.is_empty() {
+        out.push_str("entry:\n  ret i32 0\n");
+    }
+
+    let mut temps = 0;
+    let mut strings = StringPool::default();
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        let label = if id == 0 { "entry".to_string() } else { format!("bb{}", id) };
+        let _ = writeln!(out, "{}:", label);
+        for stmt in &block.statements {
+            match stmt {
+                Stmt::Return { value: Expr::Number { value, .. }, .. } => {
+                    let _ = writeln!(out, "  ret i32 {}", value);
+                }
+                Stmt::Return { value, .. } => {
+                    let result = emit_expr(&mut out, value, target, &mut temps, &mut strings);
+                    let _ = writeln!(out, "  %exit{} = trunc i64 {} to i32", temps, result);
+                    let _ = writeln!(out, "  ret i32 %exit{}", temps);
+                    temps += 1;
+                }
+            }
+        }
+    }
+
+    out.push_str("}\n");
+
+    if !strings.is_empty() {
+        out.push('\n');
+        for (index, string) in strings.iter() {
+            let _ = writeln!(
+                out,
+                "@.str{} = private constant [{} x i8] c\"{}\\00\"",
+                index,
+                string.len() + 1,
+                escape_bytes(string)
+            );
+        }
+        out.push('\n');
+        out.push_str(SPAWN_HELPER);
+    }
+
+    out
+}
+
+/// Escapes `s` for a quoted LLVM string such as a `c"..."` constant or the
+/// `source_filename`: everything but printable ASCII other
+/// than `"` and `\\` becomes a `\\XX` hex escape.
+fn escape_bytes(s: &str) -> String {
+    let mut out = String::new();
+    for byte in s.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'"' && byte != b'\\' {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "\\{:02X}", byte);
+        }
+    }
+    out
+}
+
+/// `chghel` goes through libc, which the module is linked against anyway
+/// since it defines `main`.
+const SPAWN_HELPER: &str = "\
+define internal i64 @ria_spawn(ptr %path) {
+entry:
+  %argv = alloca [2 x ptr]
+  %status = alloca i32
+  %pid = call i32 @fork()
+  %is_child = icmp eq i32 %pid, 0
+  br i1 %is_child, label %child, label %parent
+child:
+  store ptr %path, ptr %argv
+  %argv1 = getelementptr [2 x ptr], ptr %argv, i64 0, i64 1
+  store ptr null, ptr %argv1
+  %envp = alloca ptr
+  store ptr null, ptr %envp
+  call i32 @execve(ptr %path, ptr %argv, ptr %envp)
+  call void @_exit(i32 127)
+  unreachable
+parent:
+  %failed = icmp slt i32 %pid, 0
+  br i1 %failed, label %done, label %wait
+wait:
+  call i32 @waitpid(i32 %pid, ptr %status, i32 0)
+  %raw = load i32, ptr %status
+  %shifted = lshr i32 %raw, 8
+  %code = and i32 %shifted, 255
+  %result = zext i32 %code to i64
+  ret i64 %result
+done:
+  ret i64 -1
+}
+
+declare i32 @fork()
+declare i32 @execve(ptr, ptr, ptr)
+declare i32 @waitpid(i32, ptr, i32)
+declare void @_exit(i32)
+";
+
+/// The trap instruction, result register and syscall number plus argument
+/// registers for inline assembly on `arch`.
+fn syscall_abi(arch: Arch) -> (&'static str, &'static str, [&'static str; MAX_SYSCALL_ARGS]) {
+    match arch {
+        Arch::X86_64 => ("syscall", "rax", ["rax", "rdi", "rsi", "rdx", "r10", "r8", "r9"]),
+        Arch::Aarch64 => ("svc #0", "x0", ["x8", "x0", "x1", "x2", "x3", "x4", "x5"]),
+        Arch::Riscv64 => ("ecall", "a0", ["a7", "a0", "a1", "a2", "a3", "a4", "a5"]),
+    }
+}
+
+fn clock_gettime_number(arch: Arch) -> u32 {
+    match arch {
+        Arch::X86_64 => 228,
+        Arch::Aarch64 | Arch::Riscv64 => 113,
+    }
+}
+
+/// Builds the constraint string for a syscall with `operands` inputs.
+fn syscall_constraints(arch: Arch, operands: usize) -> String {
+    let (_, result, registers) = syscall_abi(arch);
+    let mut constraints = vec![format!("={{{}}}", result)];
+    constraints.extend(registers[..operands].iter().map(|r| format!("{{{}}}", r)));
+    if arch == Arch::X86_64 {
+        constraints.extend(["~{rcx}".to_string(), "~{r11}".to_string()]);
+    }
+    constraints.push("~{memory}".to_string());
+    constraints.join(",")
+}
+
+/// Emits the instructions computing `expr` as an `i64` and returns the
+/// operand that holds it. Syscalls become inline assembly, since LLVM IR
+/// has no portable way to trap into the kernel.
+fn emit_expr<'a>(
+    out: &mut String,
+    expr: &'a Expr,
+    target: &Target,
+    temps: &mut usize,
+    strings: &mut StringPool<'a>,
+) -> String {
+    match expr {
+        Expr::Number { value, .. } => value.to_string(),
+        Expr::Syscall { args, .. } => {
+            let operands: Vec<String> = args
+                .iter()
+                .map(|arg| format!("i64 {}", emit_expr(out, arg, target, temps, strings)))
+                .collect();
+
+            let (trap, _, _) = syscall_abi(target.arch);
+            let name = format!("%sys{}", temps);
+            *temps += 1;
+            let _ = writeln!(
+                out,
+                "  {} = call i64 asm sideeffect \"{}\", \"{}\"({})",
+                name,
+                trap,
+                syscall_constraints(target.arch, args.len()),
+                operands.join(", ")
+            );
+            name
+        }
+        Expr::Time { .. } => {
+            let (trap, _, _) = syscall_abi(target.arch);
+            let id = *temps;
+            *temps += 1;
+            let _ = writeln!(out, "  %ts{} = alloca [2 x i64]", id);
+            let _ = writeln!(
+                out,
+                "  call i64 asm sideeffect \"{}\", \"{}\"(i64 {}, i64 0, ptr %ts{})",
+                trap,
+                syscall_constraints(target.arch, 3),
+                clock_gettime_number(target.arch),
+                id
+            );
+            let _ = writeln!(out, "  %time{} = load i64, ptr %ts{}", id, id);
+            format!("%time{}", id)
+        }
+        Expr::Spawn { path, .. } => {
+            let name = format!("%spawn{}", temps);
+            *temps += 1;
+            let string = strings.intern(path);
+            let _ = writeln!(out, "  {} = call i64 @ria_spawn(ptr @.str{})", name, string);
+            name
+        }
+        Expr::Binary { op, lhs, rhs, .. } => {
+            let lhs = emit_expr(out, lhs, target, temps, strings);
+            let rhs = emit_expr(out, rhs, target, temps, strings);
+            let id = *temps;
+            *temps += 1;
+            let instruction = match op {
+                BinOp::Add => "add",
+                BinOp::Sub => "sub",
+                BinOp::Mul => "mul",
+                BinOp::Div => "sdiv",
+                BinOp::Rem => "srem",
+            };
+            if !matches!(op, BinOp::Div | BinOp::Rem) {
+                let _ = writeln!(out, "  %bin{} = {} i64 {}, {}", id, instruction, lhs, rhs);
+                return format!("%bin{}", id);
+            }
+
+            // Dividing by zero and i64::MIN / -1 are undefined in LLVM, so
+            // divide by 1 instead and pick the defined result afterwards:
+            // x / 0 == 0, x % 0 == x, x / -1 == -x (wrapping), x % -1 == 0.
+            let (by_zero, by_minus_one) = if *op == BinOp::Div {
+                let _ = writeln!(out, "  %neg{} = sub i64 0, {}", id, lhs);
+                ("0".to_string(), format!("%neg{}", id))
+            } else {
+                (lhs.clone(), "0".to_string())
+            };
+            let _ = writeln!(out, "  %zero{} = icmp eq i64 {}, 0", id, rhs);
+            let _ = writeln!(out, "  %minus_one{} = icmp eq i64 {}, -1", id, rhs);
+            let _ = writeln!(out, "  %unsafe{} = or i1 %zero{}, %minus_one{}", id, id, id);
+            let _ = writeln!(out, "  %divisor{} = select i1 %unsafe{}, i64 1, i64 {}", id, id, rhs);
+            let _ = writeln!(out, "  %raw{} = {} i64 {}, %divisor{}", id, instruction, lhs, id);
+            let _ = writeln!(
+                out,
+                "  %signed{} = select i1 %minus_one{}, i64 {}, i64 %raw{}",
+                id, id, by_minus_one, id
+            );
+            let _ = writeln!(
+                out,
+                "  %bin{} = select i1 %zero{}, i64 {}, i64 %signed{}",
+                id, id, by_zero, id
+            );
+            format!("%bin{}", id)
+        }
+    }
+}
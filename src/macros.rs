@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::diagnostics::{self, Diagnostic};
+use crate::lexer::{Span, Token, TokenType};
+use crate::limits::{self, Limits};
+
+/// A `makro NAME(params) body` definition. The body runs to the end of the
+/// line the definition starts on.
+struct Macro {
+    params: Option<Vec<String>>,
+    body: Vec<Token>,
+}
+
+fn error(message: impl Into<String>) -> Diagnostic {
+    Diagnostic::new(diagnostics::E_MACRO, message)
+}
+
+fn is_ident(token: &Token) -> bool {
+    token.token_type == TokenType::Ident
+}
+
+fn ident_name(token: &Token) -> &str {
+    token.value.as_deref().unwrap_or_default()
+}
+
+/// Removes `makro` definitions from the token stream and expands every use.
+///
+/// The rules are deliberately small:
+///
+/// * A macro must be defined before it is used and cannot be redefined.
+/// * Only whole identifier tokens that name a parameter are substituted.
+/// * Arguments are expanded before they are substituted, and the result is
+///   scanned again for further macro uses.
+/// * A macro is never expanded inside its own expansion, so recursion stops
+///   instead of looping forever.
+/// * Expanded tokens take the span of the use site, so errors point at the
+///   line that used the macro rather than at its definition.
+///
+/// Since a macro can use another several times, a few lines can expand
+/// into an enormous program; expansion stops with E0019 once the result
+/// has more than `limits.tokens` tokens, or once uses nest inside each
+/// other, through bodies or arguments, more than `limits.depth` deep.
+pub fn expand(tokens: Vec<Token>, source: &str, limits: &Limits) -> Result<Vec<Token>, Diagnostic> {
+    let mut macros = HashMap::new();
+    let mut body = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i].token_type == TokenType::Makro {
+            let span = tokens[i].span;
+            let (name, definition, next) =
+                parse_definition(&tokens, i, source).map_err(|e| e.at(span))?;
+            if macros.insert(name.clone(), definition).is_some() {
+                let message = format!("Macro '{}' is defined more than once", name);
+                return Err(error(message).at(span));
+            }
+            i = next;
+        } else {
+            // Expand uses as we go so a macro is only visible after its definition.
+            let end = tokens[i..]
+                .iter()
+                .position(|token| token.token_type == TokenType::Makro)
+                .map_or(tokens.len(), |offset| i + offset);
+            let budget = Budget { limits, produced: body.len(), depth: 0 };
+            body.extend(expand_tokens(&tokens[i..end], &macros, &mut Vec::new(), budget)?);
+            i = end;
+        }
+    }
+
+    Ok(body)
+}
+
+fn parse_definition(
+    tokens: &[Token],
+    start: usize,
+    source: &str,
+) -> Result<(String, Macro, usize), Diagnostic> {
+    let line_start = tokens[start].span.start;
+    let on_same_line = |token: &Token| !source[line_start..token.span.start].contains('\n');
+
+    let name = match tokens.get(start + 1) {
+        Some(token) if is_ident(token) && on_same_line(token) => ident_name(token).to_string(),
+        _ => return Err(error("Expected a macro name after 'makro'")),
+    };
+
+    let mut i = start + 2;
+    let mut params = None;
+    if tokens.get(i).is_some_and(|token| token.token_type == TokenType::LParen) {
+        let mut names: Vec<String> = Vec::new();
+        i += 1;
+        loop {
+            match tokens.get(i).map(|token| &token.token_type) {
+                Some(TokenType::RParen) if names.is_empty() => break,
+                Some(TokenType::Ident) => {
+                    let param = ident_name(&tokens[i]).to_string();
+                    if names.contains(&param) {
+                        return Err(error(format!(
+                            "Parameter '{}' appears twice in macro '{}'",
+                            param, name
+                        )));
+                    }
+                    names.push(param);
+                    i += 1;
+                    match tokens.get(i).map(|token| &token.token_type) {
+                        Some(TokenType::Comma) => i += 1,
+                        Some(TokenType::RParen) => break,
+                        _ => return Err(error(format!("Expected ',' or ')' in macro '{}'", name))),
+                    }
+                }
+                _ => return Err(error(format!("Expected a parameter name in macro '{}'", name))),
+            }
+        }
+        i += 1;
+        params = Some(names);
+    }
+
+    let body_end = tokens[i..]
+        .iter()
+        .position(|token| !on_same_line(token))
+        .map_or(tokens.len(), |offset| i + offset);
+    let body = tokens[i..body_end].to_vec();
+
+    Ok((name, Macro { params, body }, body_end))
+}
+
+/// Splits the argument list that starts at the `(` at `tokens[open]`,
+/// returning the arguments and the index just past the closing `)`.
+fn parse_arguments(
+    tokens: &[Token],
+    open: usize,
+    name: &str,
+) -> Result<(Vec<Vec<Token>>, usize), Diagnostic> {
+    let mut args = vec![Vec::new()];
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+        match token.token_type {
+            TokenType::RParen if depth == 0 => {
+                if args.len() == 1 && args[0].is_empty() {
+                    args.clear();
+                }
+                return Ok((args, i + 1));
+            }
+            TokenType::Comma if depth == 0 => args.push(Vec::new()),
+            _ => {
+                match token.token_type {
+                    TokenType::LParen => depth += 1,
+                    TokenType::RParen => depth -= 1,
+                    _ => {}
+                }
+                args.last_mut().unwrap().push(token.clone());
+            }
+        }
+    }
+
+    Err(error(format!("Unclosed argument list for macro '{}'", name)))
+}
+
+fn expand_tokens(
+    tokens: &[Token],
+    macros: &HashMap<String, Macro>,
+    active: &mut Vec<String>,
+    budget: Budget,
+) -> Result<Vec<Token>, Diagnostic> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let name = ident_name(token);
+        let definition = match macros.get(name) {
+            Some(definition) if is_ident(token) && !active.iter().any(|a| a == name) => definition,
+            _ => {
+                out.push(token.clone());
+                budget.check_tokens(out.len())?;
+                i += 1;
+                continue;
+            }
+        };
+
+        budget.check_depth(name, token.span.start).map_err(|e| e.at(token.span))?;
+        let mut bindings = HashMap::new();
+        let mut end = i + 1;
+        if let Some(params) = &definition.params {
+            if tokens.get(end).map(|token| &token.token_type) != Some(&TokenType::LParen) {
+                return Err(error(format!("Macro '{}' expects arguments", name)).at(token.span));
+            }
+            let (args, after) =
+                parse_arguments(tokens, end, name).map_err(|e| e.at(token.span))?;
+            if args.len() != params.len() {
+                return Err(error(format!(
+                    "Macro '{}' takes {} argument(s) but {} were given",
+                    name,
+                    params.len(),
+                    args.len()
+                ))
+                .at(token.span));
+            }
+            for (param, arg) in params.iter().zip(args) {
+                let expanded = expand_tokens(&arg, macros, active, budget.nested(out.len()))?;
+                bindings.insert(param.as_str(), expanded);
+            }
+            end = after;
+        }
+
+        let span = Span { start: token.span.start, end: tokens[end - 1].span.end };
+        let mut substituted = Vec::new();
+        for body_token in &definition.body {
+            match bindings.get(ident_name(body_token)) {
+                Some(arg) if is_ident(body_token) => substituted.extend(arg.iter().cloned()),
+                _ => substituted.push(body_token.clone()),
+            }
+            budget.check_tokens(out.len() + substituted.len())?;
+        }
+        for substituted_token in &mut substituted {
+            substituted_token.span = span;
+        }
+
+        active.push(name.to_string());
+        let inner = budget.nested(out.len());
+        out.extend(expand_tokens(&substituted, macros, active, inner)?);
+        active.pop();
+
+        i = end;
+    }
+
+    Ok(out)
+}
+
+/// What is left of the limits for the tokens being expanded: how many
+/// tokens were produced before them, and how deeply they sit inside other
+/// macro uses.
+#[derive(Clone, Copy)]
+struct Budget<'a> {
+    limits: &'a Limits,
+    produced: usize,
+    depth: usize,
+}
+
+impl Budget<'_> {
+    /// The budget for expanding a use nested one level deeper, after
+    /// `produced` more tokens.
+    fn nested(self, produced: usize) -> Self {
+        Budget { produced: self.produced + produced, depth: self.depth + 1, ..self }
+    }
+
+    /// Checks that `count` more tokens still fit.
+    fn check_tokens(&self, count: usize) -> Result<(), Diagnostic> {
+        if self.produced + count <= self.limits.tokens {
+            return Ok(());
+        }
+        Err(limits::exceeded(
+            format!(
+                "The program has more than {} tokens after macro expansion",
+                self.limits.tokens
+            ),
+            "tokens",
+        ))
+    }
+
+    /// Checks that a use of `name` at byte `start` may be expanded here.
+    fn check_depth(&self, name: &str, start: usize) -> Result<(), Diagnostic> {
+        if self.depth < self.limits.depth {
+            return Ok(());
+        }
+        Err(limits::exceeded(
+            format!(
+                "Macro '{}' at byte {} is used more than {} levels deep inside other macros",
+                name, start, self.limits.depth
+            ),
+            "depth",
+        ))
+    }
+}
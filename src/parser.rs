@@ -0,0 +1,383 @@
+use crate::ast::{BinOp, Expr, MAX_SYSCALL_ARGS, Program, Stmt};
+use crate::diagnostics::{self, Diagnostic};
+use crate::lexer::{Span, Token, TokenType};
+use crate::limits::{self, Limits};
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    max_depth: usize,
+    /// How many `parse_binary` calls are under way, which bounds how deep
+    /// the parser recurses.
+    nesting: usize,
+    /// How deeply the expression parsed last nests.
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser { tokens, pos: 0, max_depth: Limits::default().depth, nesting: 0, depth: 0 }
+    }
+
+    /// Rejects expressions that nest deeper than `max_depth`, counting
+    /// parentheses, builtin arguments and operators.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn parse_program(mut self) -> Result<Program, Diagnostic> {
+        let mut statements = Vec::new();
+
+        while self.pos < self.tokens.len() {
+            let token = &self.tokens[self.pos];
+            if let TokenType::Return = token.token_type {
+                let statement = self.parse_return();
+                statements.push(statement.map_err(|error| self.locate(error))?);
+            } else if let Some(error) = misspelling(token, "keyword", STATEMENT_KEYWORDS) {
+                return Err(error);
+            } else {
+                return Err(Diagnostic::new(
+                    diagnostics::E_UNEXPECTED_TOKEN,
+                    format!(
+                        "Unexpected {} at byte {}: expected 'kharrej'",
+                        describe(token),
+                        token.span.start
+                    ),
+                )
+                .at(token.span));
+            }
+        }
+
+        Ok(Program { statements })
+    }
+
+    /// Points an error that does not say where it is at the token the
+    /// parser stopped on, or at the last token when it ran off the end.
+    fn locate(&self, error: Diagnostic) -> Diagnostic {
+        if error.span.is_some() {
+            return error;
+        }
+        match self.tokens.get(self.pos).or(self.tokens.last()) {
+            Some(token) => error.at(token.span),
+            None => error,
+        }
+    }
+
+    fn parse_return(&mut self) -> Result<Stmt, Diagnostic> {
+        let start = self.tokens[self.pos].span.start;
+
+        if self.pos + 2 >= self.tokens.len() {
+            return Err(Diagnostic::new(
+                diagnostics::E_INCOMPLETE_RETURN,
+                "Incomplete return statement: expected 'kharrej <number>;'",
+            ));
+        }
+
+        self.pos += 1;
+        let value = self.parse_expr(&|| {
+            Diagnostic::new(diagnostics::E_EXPECTED_NUMBER, "Expected number after 'kharrej'")
+        })?;
+        if let Expr::Number { value: exit_code, span } = value {
+            check_exit_code(exit_code).map_err(|error| error.at(span))?;
+        }
+        self.pos += 1;
+
+        match self.tokens.get(self.pos) {
+            Some(semi) if semi.token_type == TokenType::Semi => {
+                let span = Span { start, end: semi.span.end };
+                self.pos += 1;
+                Ok(Stmt::Return { value, span })
+            }
+            Some(_) => Err(Diagnostic::new(
+                diagnostics::E_MISSING_SEMI,
+                "Expected semicolon after number",
+            )),
+            None => Err(Diagnostic::new(
+                diagnostics::E_INCOMPLETE_RETURN,
+                "Incomplete return statement: expected 'kharrej <number>;'",
+            )),
+        }
+    }
+
+    /// Parses an expression and leaves `pos` on its last token. `expected`
+    /// is the error to report when no operand starts at `pos`.
+    fn parse_expr(&mut self, expected: &dyn Fn() -> Diagnostic) -> Result<Expr, Diagnostic> {
+        self.parse_binary(0, expected)
+    }
+
+    /// Parses operands joined by operators that bind tighter than
+    /// `min_power`, following the binding powers in `BINARY_OPERATORS`.
+    fn parse_binary(
+        &mut self,
+        min_power: u8,
+        expected: &dyn Fn() -> Diagnostic,
+    ) -> Result<Expr, Diagnostic> {
+        self.nesting += 1;
+        self.check_depth(self.nesting)?;
+        let mut lhs = self.parse_operand(expected)?;
+        let mut depth = self.depth;
+
+        while let Some(&(_, op, power)) = self.tokens.get(self.pos + 1).and_then(binary_operator) {
+            if power <= min_power {
+                break;
+            }
+            self.pos += 2;
+            let rhs = self.parse_binary(power, &|| {
+                Diagnostic::new(
+                    diagnostics::E_EXPECTED_OPERAND,
+                    format!("Expected a number or a builtin call after '{}'", op.symbol()),
+                )
+            })?;
+            // Operators group to the left, so a long chain deepens the tree
+            // without the parser recursing.
+            depth = depth.max(self.depth) + 1;
+            self.check_depth(depth)?;
+            let span = Span { start: lhs.span().start, end: rhs.span().end };
+            lhs = Expr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs), span };
+        }
+
+        self.nesting -= 1;
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), Diagnostic> {
+        if depth <= self.max_depth {
+            return Ok(());
+        }
+        let start = self.tokens.get(self.pos).map_or(0, |token| token.span.start);
+        Err(limits::exceeded(
+            format!(
+                "Expression at byte {} nests more than {} levels deep",
+                start, self.max_depth
+            ),
+            "depth",
+        ))
+    }
+
+    /// Parses a number, a builtin call or a parenthesized expression; leaves
+    /// `pos` on its last token.
+    fn parse_operand(&mut self, expected: &dyn Fn() -> Diagnostic) -> Result<Expr, Diagnostic> {
+        self.depth = 1;
+        match self.tokens.get(self.pos) {
+            Some(token) if token.token_type == TokenType::LParen => self.parse_group(),
+            Some(token) if token.token_type == TokenType::Number => {
+                // Tokens built by hand skip the lexer's range check.
+                let value = token.value.as_deref().and_then(|value| value.parse().ok());
+                let Some(value) = value else {
+                    return Err(Diagnostic::new(
+                        diagnostics::E_INVALID_NUMBER,
+                        format!("Invalid integer literal at byte {}", token.span.start),
+                    )
+                    .at(token.span));
+                };
+                Ok(Expr::Number { value, span: token.span })
+            }
+            Some(token) => match builtin(token) {
+                Some("syscall") => self.parse_syscall(),
+                Some("waqt") => self.parse_time(),
+                Some("chghel") => self.parse_spawn(),
+                _ => Err(misspelling(token, "builtin", BUILTINS).unwrap_or_else(expected)),
+            },
+            None => Err(expected()),
+        }
+    }
+
+    /// Parses `( expr )` starting at the `(` and leaves `pos` on the `)`.
+    fn parse_group(&mut self) -> Result<Expr, Diagnostic> {
+        let open = self.tokens[self.pos].span;
+        self.pos += 1;
+        let inner = self.parse_expr(&|| {
+            Diagnostic::new(
+                diagnostics::E_EXPECTED_OPERAND,
+                "Expected a number or a builtin call after '('",
+            )
+        })?;
+
+        self.pos += 1;
+        match self.tokens.get(self.pos) {
+            Some(token) if token.token_type == TokenType::RParen => Ok(inner),
+            _ => Err(Diagnostic::new(
+                diagnostics::E_UNCLOSED_PAREN,
+                format!("Unclosed '(' at byte {}: expected ')'", open.start),
+            )
+            .at(open)),
+        }
+    }
+
+    /// Parses `syscall(num, a, b, ...)` starting at the `syscall` token and
+    /// leaves `pos` on the closing parenthesis.
+    fn parse_syscall(&mut self) -> Result<Expr, Diagnostic> {
+        let start = self.tokens[self.pos].span.start;
+        let token_type = |parser: &Self, pos: usize| {
+            parser.tokens.get(pos).map(|token| token.token_type.clone())
+        };
+
+        self.pos += 1;
+        if token_type(self, self.pos) != Some(TokenType::LParen) {
+            return Err(syscall_error("Expected '(' after 'syscall'"));
+        }
+
+        let mut args = Vec::new();
+        let mut depth = 0;
+        loop {
+            self.pos += 1;
+            args.push(self.parse_expr(&|| {
+                syscall_error("Expected a number or a builtin call as a syscall argument")
+            })?);
+            depth = depth.max(self.depth);
+            self.pos += 1;
+            match token_type(self, self.pos) {
+                Some(TokenType::Comma) => {}
+                Some(TokenType::RParen) => break,
+                _ => return Err(syscall_error("Expected ',' or ')' in syscall arguments")),
+            }
+        }
+
+        if args.len() > MAX_SYSCALL_ARGS {
+            return Err(syscall_error(format!(
+                "syscall takes a number and at most {} arguments, got {}",
+                MAX_SYSCALL_ARGS - 1,
+                args.len() - 1
+            )));
+        }
+
+        self.depth = depth + 1;
+        self.check_depth(self.depth)?;
+        let span = Span { start, end: self.tokens[self.pos].span.end };
+        Ok(Expr::Syscall { args, span })
+    }
+
+    /// Parses `waqt()` starting at the `waqt` token and leaves `pos` on the
+    /// closing parenthesis.
+    fn parse_time(&mut self) -> Result<Expr, Diagnostic> {
+        let start = self.tokens[self.pos].span.start;
+        let open = self.tokens.get(self.pos + 1).map(|token| &token.token_type);
+        let close = self.tokens.get(self.pos + 2).map(|token| &token.token_type);
+        if open != Some(&TokenType::LParen) || close != Some(&TokenType::RParen) {
+            return Err(Diagnostic::new(
+                diagnostics::E_BUILTIN_ARGS,
+                "'waqt' takes no arguments: expected 'waqt()'",
+            ));
+        }
+
+        self.pos += 2;
+        let span = Span { start, end: self.tokens[self.pos].span.end };
+        Ok(Expr::Time { span })
+    }
+
+    /// Parses `chghel("path")` starting at the `chghel` token and leaves
+    /// `pos` on the closing parenthesis.
+    fn parse_spawn(&mut self) -> Result<Expr, Diagnostic> {
+        let start = self.tokens[self.pos].span.start;
+        let kinds = self
+            .tokens
+            .get(self.pos + 1..self.pos + 4)
+            .map(|args| [&args[0].token_type, &args[1].token_type, &args[2].token_type]);
+        let path = match kinds {
+            Some([TokenType::LParen, TokenType::Str, TokenType::RParen]) => {
+                self.tokens[self.pos + 2].value.clone().unwrap_or_default()
+            }
+            _ => {
+                return Err(Diagnostic::new(
+                    diagnostics::E_BUILTIN_ARGS,
+                    "'chghel' takes one string: expected 'chghel(\"/path/to/program\")'",
+                ));
+            }
+        };
+        if path.contains('\0') {
+            return Err(Diagnostic::new(
+                diagnostics::E_BUILTIN_ARGS,
+                "The program path passed to 'chghel' cannot contain NUL",
+            ));
+        }
+
+        self.pos += 3;
+        let span = Span { start, end: self.tokens[self.pos].span.end };
+        Ok(Expr::Spawn { path, span })
+    }
+}
+
+/// Binary operators with their binding powers: the higher the power, the
+/// tighter the operator binds. All of them are left-associative, so adding
+/// an operator to the grammar only takes a new row here.
+const BINARY_OPERATORS: &[(TokenType, BinOp, u8)] = &[
+    (TokenType::Plus, BinOp::Add, 10),
+    (TokenType::Minus, BinOp::Sub, 10),
+    (TokenType::Star, BinOp::Mul, 20),
+    (TokenType::Slash, BinOp::Div, 20),
+    (TokenType::Percent, BinOp::Rem, 20),
+];
+
+fn binary_operator(token: &Token) -> Option<&'static (TokenType, BinOp, u8)> {
+    BINARY_OPERATORS.iter().find(|(token_type, _, _)| *token_type == token.token_type)
+}
+
+/// The keywords a statement can start with.
+const STATEMENT_KEYWORDS: &[&str] = &["kharrej", "makro"];
+
+const BUILTINS: &[&str] = &["syscall", "waqt", "chghel"];
+
+/// Returns the builtin `token` names, if it names one.
+fn builtin(token: &Token) -> Option<&str> {
+    match (&token.token_type, token.value.as_deref()) {
+        (TokenType::Ident, Some(name)) if BUILTINS.contains(&name) => Some(name),
+        _ => None,
+    }
+}
+
+/// Reports an identifier that is a near miss for one of `words`, naming the
+/// closest as the fix.
+fn misspelling(token: &Token, kind: &str, words: &[&str]) -> Option<Diagnostic> {
+    if token.token_type != TokenType::Ident {
+        return None;
+    }
+    let name = token.value.as_deref()?;
+    let suggestion = diagnostics::closest(name, words)?;
+    Some(
+        Diagnostic::new(
+            diagnostics::E_UNKNOWN_KEYWORD,
+            format!(
+                "Unknown {} '{}' at byte {}: did you mean '{}'?",
+                kind, name, token.span.start, suggestion
+            ),
+        )
+        .at(token.span),
+    )
+}
+
+/// How a token is named in diagnostics.
+fn describe(token: &Token) -> String {
+    let value = token.value.as_deref().unwrap_or_default();
+    match token.token_type {
+        TokenType::Return => "'kharrej'".to_string(),
+        TokenType::Makro => "'makro'".to_string(),
+        TokenType::Ident => format!("'{}'", value),
+        TokenType::Number => format!("number {}", value),
+        TokenType::Str => "string literal".to_string(),
+        TokenType::Semi => "';'".to_string(),
+        TokenType::LParen => "'('".to_string(),
+        TokenType::RParen => "')'".to_string(),
+        TokenType::Comma => "','".to_string(),
+        TokenType::Plus => "'+'".to_string(),
+        TokenType::Minus => "'-'".to_string(),
+        TokenType::Star => "'*'".to_string(),
+        TokenType::Slash => "'/'".to_string(),
+        TokenType::Percent => "'%'".to_string(),
+    }
+}
+
+fn syscall_error(message: impl Into<String>) -> Diagnostic {
+    Diagnostic::new(diagnostics::E_SYSCALL, message)
+}
+
+fn check_exit_code(exit_code: i32) -> Result<(), Diagnostic> {
+    if (0..=255).contains(&exit_code) {
+        return Ok(());
+    }
+    Err(Diagnostic::new(
+        diagnostics::E_EXIT_CODE_RANGE,
+        format!("Exit code must be between 0 and 255, got {}", exit_code),
+    ))
+}
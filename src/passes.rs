@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::ast::Program;
+use crate::diagnostics::Diagnostic;
+
+/// A user-supplied compiler pass. Implement whichever hooks the pass needs;
+/// the defaults leave the program untouched.
+pub trait Pass {
+    /// Identifies the pass for ordering and enable/disable controls.
+    fn name(&self) -> &str;
+
+    /// Runs on the syntax tree after parsing. Returning an error aborts the
+    /// compilation with that diagnostic, which is how lints reject programs.
+    fn run_ast(&self, _program: &mut Program) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    /// Runs on the generated assembly after the built-in peephole optimizer.
+    fn run_asm(&self, asm_code: String) -> String {
+        asm_code
+    }
+}
+
+struct Entry {
+    pass: Box<dyn Pass>,
+    enabled: bool,
+}
+
+/// An ordered list of passes. Passes run in registration order and can be
+/// switched off by name without removing them.
+#[derive(Default)]
+pub struct PassManager {
+    entries: Vec<Entry>,
+}
+
+impl PassManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `pass` to the end of the pipeline.
+    pub fn add(&mut self, pass: impl Pass + 'static) {
+        self.entries.push(Entry { pass: Box::new(pass), enabled: true });
+    }
+
+    /// Inserts `pass` right before the pass called `before`.
+    pub fn insert_before(&mut self, before: &str, pass: impl Pass + 'static) -> Result<(), String> {
+        let index = self.position(before)?;
+        self.entries.insert(index, Entry { pass: Box::new(pass), enabled: true });
+        Ok(())
+    }
+
+    /// Inserts `pass` right after the pass called `after`.
+    pub fn insert_after(&mut self, after: &str, pass: impl Pass + 'static) -> Result<(), String> {
+        let index = self.position(after)?;
+        self.entries.insert(index + 1, Entry { pass: Box::new(pass), enabled: true });
+        Ok(())
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let index = self.position(name)?;
+        self.entries[index].enabled = enabled;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.enabled && entry.pass.name() == name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Names of all registered passes, in the order they run.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.pass.name())
+    }
+
+    pub fn run_ast(&self, program: &mut Program) -> Result<(), Diagnostic> {
+        for entry in self.enabled() {
+            entry.pass.run_ast(program)?;
+        }
+        Ok(())
+    }
+
+    pub fn run_asm(&self, asm_code: String) -> String {
+        self.enabled().fold(asm_code, |asm_code, entry| entry.pass.run_asm(asm_code))
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|entry| entry.enabled)
+    }
+
+    fn position(&self, name: &str) -> Result<usize, String> {
+        self.entries
+            .iter()
+            .position(|entry| entry.pass.name() == name)
+            .ok_or_else(|| format!("No pass named '{}'", name))
+    }
+}
+
+impl fmt::Debug for PassManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|entry| (entry.pass.name(), entry.enabled)))
+            .finish()
+    }
+}
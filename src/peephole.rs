@@ -0,0 +1,117 @@
+struct Instruction<'a> {
+    mnemonic: &'a str,
+    operands: Vec<&'a str>,
+    comment: Option<&'a str>,
+}
+
+fn parse_instruction(line: &str) -> Option<Instruction<'_>> {
+    if !line.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    let (code, comment) = match line.split_once(';') {
+        Some((code, comment)) => (code.trim(), Some(comment.trim())),
+        None => (line.trim(), None),
+    };
+    if code.is_empty() {
+        return None;
+    }
+
+    let (mnemonic, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+    let operands = rest
+        .split(',')
+        .map(str::trim)
+        .filter(|operand| !operand.is_empty())
+        .collect();
+
+    Some(Instruction {
+        mnemonic,
+        operands,
+        comment,
+    })
+}
+
+fn label_name(line: &str) -> Option<&str> {
+    if line.starts_with([' ', '\t']) {
+        return None;
+    }
+    line.trim().strip_suffix(':')
+}
+
+fn format_instruction(instruction: &str, comment: Option<&str>) -> String {
+    match comment {
+        Some(comment) => format!("    {:<15} ; {}", instruction, comment),
+        None => format!("    {}", instruction),
+    }
+}
+
+fn low_dword(register: &str) -> Option<String> {
+    match register {
+        "rax" | "rbx" | "rcx" | "rdx" | "rsi" | "rdi" | "rbp" | "rsp" => {
+            Some(format!("e{}", &register[1..]))
+        }
+        "r8" | "r9" | "r10" | "r11" | "r12" | "r13" | "r14" | "r15" => {
+            Some(format!("{}d", register))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the replacement for the sequence starting at `line`, together with
+/// how many input lines it consumes, or `None` if no rule applies.
+fn rewrite(line: &str, next: Option<&str>) -> Option<(Option<String>, usize)> {
+    let instruction = parse_instruction(line)?;
+
+    match (instruction.mnemonic, instruction.operands.as_slice()) {
+        ("mov", [register, "0"]) => {
+            let low = low_dword(register)?;
+            let xor = format!("xor {}, {}", low, low);
+            Some((Some(format_instruction(&xor, instruction.comment)), 1))
+        }
+        ("push", [source]) => {
+            let pop = next.and_then(parse_instruction)?;
+            let ("pop", [destination]) = (pop.mnemonic, pop.operands.as_slice()) else {
+                return None;
+            };
+            if source == destination {
+                return Some((None, 2));
+            }
+            let mov = format!("mov {}, {}", destination, source);
+            Some((Some(format_instruction(&mov, instruction.comment)), 2))
+        }
+        ("jmp", [target]) if next.and_then(label_name) == Some(*target) => Some((None, 1)),
+        _ => None,
+    }
+}
+
+/// Rewrites obviously redundant instruction sequences in generated assembly:
+///
+/// * `mov reg, 0` becomes `xor reg32, reg32`, which is shorter and also clears
+///   the upper half of the 64-bit register.
+/// * `push x` directly followed by `pop y` becomes `mov y, x`, or disappears
+///   when `x` and `y` are the same.
+/// * `jmp label` directly followed by `label:` is removed.
+pub fn optimize(asm_code: &str) -> String {
+    let lines: Vec<&str> = asm_code.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        match rewrite(lines[i], lines.get(i + 1).copied()) {
+            Some((replacement, consumed)) => {
+                out.extend(replacement);
+                i += consumed;
+            }
+            None => {
+                out.push(lines[i].to_string());
+                i += 1;
+            }
+        }
+    }
+
+    let mut optimized = out.join("\n");
+    if asm_code.ends_with('\n') {
+        optimized.push('\n');
+    }
+    optimized
+}
@@ -0,0 +1,150 @@
+use std::process::Command;
+
+use crate::cli::{CrateType, Emit, Options};
+use crate::json;
+use crate::toolchain;
+
+/// One command the build runs, with the files it reads and writes.
+struct Step {
+    kind: &'static str,
+    arguments: Vec<String>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+fn command_line(command: &Command) -> Vec<String> {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn string_array(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| json::string(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// The files building `options.input` writes, named after `output_name`:
+/// what the selected `--emit` kind writes, or every artifact of a native
+/// build. Running in memory with `--jit` writes nothing.
+pub fn outputs(options: &Options, output_name: &str) -> Vec<String> {
+    match options.emit {
+        Some(Emit::Cfg) => return vec![format!("{}.dot", output_name)],
+        Some(Emit::Bytecode) => return vec![format!("{}.riac", output_name)],
+        Some(_) => return Vec::new(),
+        None if options.jit => return Vec::new(),
+        None => {}
+    }
+
+    let mut outputs = vec![
+        format!("{}.{}", output_name, options.target.asm_extension()),
+        format!("{}.o", output_name),
+    ];
+    if !options.compile_only {
+        outputs.push(match options.crate_type {
+            CrateType::Bin => output_name.to_string(),
+            CrateType::Staticlib => format!("lib{}.a", output_name),
+            CrateType::Cdylib => format!("lib{}.so", output_name),
+        });
+    }
+    outputs
+}
+
+/// Describes what building `options.input` runs and produces, without
+/// running anything, as a one-entry array in the compile_commands.json
+/// layout. Besides the usual `directory`, `file`, `arguments` and `output`,
+/// the entry lists every `steps` command in order and the `artifacts` the
+/// build leaves behind. `arguments` is the compiler invocation that runs the
+/// whole build; it is also the `compile` step, which writes the assembly.
+/// With `-c` the plan ends at the object file.
+pub fn build_plan(
+    options: &Options,
+    output_name: &str,
+    directory: &str,
+    arguments: &[String],
+) -> String {
+    let target = &options.target;
+    let asm_file = format!("{}.{}", output_name, target.asm_extension());
+    let obj_file = format!("{}.o", output_name);
+
+    let mut steps = vec![
+        Step {
+            kind: "compile",
+            arguments: arguments.to_vec(),
+            inputs: vec![options.input.clone()],
+            outputs: vec![asm_file.clone()],
+        },
+        Step {
+            kind: "assemble",
+            arguments: command_line(&toolchain::assemble_command(&asm_file, &obj_file, target)),
+            inputs: vec![asm_file],
+            outputs: vec![obj_file.clone()],
+        },
+    ];
+    let output = match options.crate_type {
+        _ if options.compile_only => obj_file,
+        CrateType::Bin => {
+            let link = toolchain::link_command(&obj_file, output_name, &options.link, target);
+            let mut inputs = vec![obj_file];
+            inputs.extend(options.link.objects.iter().cloned());
+            steps.push(Step {
+                kind: "link",
+                arguments: command_line(&link),
+                inputs,
+                outputs: vec![output_name.to_string()],
+            });
+            output_name.to_string()
+        }
+        CrateType::Staticlib => {
+            let lib_file = format!("lib{}.a", output_name);
+            steps.push(Step {
+                kind: "archive",
+                arguments: command_line(&toolchain::archive_command(&obj_file, &lib_file)),
+                inputs: vec![obj_file],
+                outputs: vec![lib_file.clone()],
+            });
+            lib_file
+        }
+        CrateType::Cdylib => {
+            let lib_file = format!("lib{}.so", output_name);
+            let link =
+                toolchain::link_shared_command(&obj_file, &lib_file, &options.link, target);
+            let mut inputs = vec![obj_file];
+            inputs.extend(options.link.objects.iter().cloned());
+            steps.push(Step {
+                kind: "link",
+                arguments: command_line(&link),
+                inputs,
+                outputs: vec![lib_file.clone()],
+            });
+            lib_file
+        }
+    };
+
+    let artifacts = outputs(options, output_name);
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            format!(
+                "      {{\"kind\": {}, \"arguments\": {}, \"inputs\": {}, \"outputs\": {}}}",
+                json::string(step.kind),
+                string_array(&step.arguments),
+                string_array(&step.inputs),
+                string_array(&step.outputs)
+            )
+        })
+        .collect();
+
+    format!(
+        "[\n  {{\n    \"directory\": {},\n    \"file\": {},\n    \"arguments\": {},\n    \
+         \"output\": {},\n    \"target\": {},\n    \"steps\": [\n{}\n    ],\n    \
+         \"artifacts\": {}\n  }}\n]",
+        json::string(directory),
+        json::string(&options.input),
+        string_array(arguments),
+        json::string(&output),
+        json::string(&target.to_string()),
+        steps.join(",\n"),
+        string_array(&artifacts)
+    )
+}
@@ -0,0 +1,157 @@
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+
+use crate::ast::{Expr, Program, Stmt};
+use crate::diagnostics::{self, Diagnostic};
+use crate::passes::Pass;
+
+/// Bumped whenever [`PluginVTable`] changes layout.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol every plugin exports:
+/// `const struct RiaPluginVTable *ria_plugin_init(void);`
+pub const PLUGIN_ENTRY: &str = "ria_plugin_init";
+
+/// The table a plugin hands back from its entry point. Mirrors this C struct:
+///
+/// ```c
+/// struct RiaPluginVTable {
+///     uint32_t abi_version;
+///     const char *name;
+///     const char *(*check_return)(int32_t value, size_t offset);
+///     char *(*transform_asm)(const char *asm_code);
+/// };
+/// ```
+///
+/// `check_return` is called for every `kharrej` with a constant exit code,
+/// passing the code and the statement's byte offset, and returns an error
+/// message, or null to accept the statement.
+/// `transform_asm` returns replacement assembly allocated with `malloc`, or
+/// null to leave it unchanged. Either hook may be null.
+#[repr(C)]
+pub struct PluginVTable {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub check_return: Option<extern "C" fn(value: i32, offset: usize) -> *const c_char>,
+    pub transform_asm: Option<extern "C" fn(asm_code: *const c_char) -> *mut c_char>,
+}
+
+const RTLD_NOW: c_int = 2;
+
+#[link(name = "dl")]
+unsafe extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlerror() -> *const c_char;
+}
+
+unsafe extern "C" {
+    fn free(ptr: *mut c_void);
+}
+
+fn last_dl_error() -> String {
+    unsafe {
+        let error = dlerror();
+        if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(error).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// A loaded shared library plugin. The library stays mapped for the rest of
+/// the process, so the vtable never dangles.
+pub struct Plugin {
+    name: String,
+    vtable: &'static PluginVTable,
+}
+
+impl Plugin {
+    pub fn load(path: &str) -> Result<Self, String> {
+        // dlopen only searches the library path for bare names.
+        let path = if path.contains('/') { path.to_string() } else { format!("./{}", path) };
+        let c_path = CString::new(path.as_str()).map_err(|_| "path contains a NUL byte".to_string())?;
+        let c_entry = CString::new(PLUGIN_ENTRY).unwrap();
+
+        unsafe {
+            let handle = dlopen(c_path.as_ptr(), RTLD_NOW);
+            if handle.is_null() {
+                return Err(last_dl_error());
+            }
+
+            let entry = dlsym(handle, c_entry.as_ptr());
+            if entry.is_null() {
+                return Err(format!("missing entry point '{}'", PLUGIN_ENTRY));
+            }
+
+            let init: extern "C" fn() -> *const PluginVTable = std::mem::transmute(entry);
+            let vtable = init()
+                .as_ref()
+                .ok_or_else(|| format!("'{}' returned null", PLUGIN_ENTRY))?;
+            if vtable.abi_version != PLUGIN_ABI_VERSION {
+                return Err(format!(
+                    "plugin ABI version {} does not match the compiler's {}",
+                    vtable.abi_version, PLUGIN_ABI_VERSION
+                ));
+            }
+
+            let name = if vtable.name.is_null() {
+                path
+            } else {
+                CStr::from_ptr(vtable.name).to_string_lossy().into_owned()
+            };
+
+            Ok(Plugin { name, vtable })
+        }
+    }
+}
+
+impl Pass for Plugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn run_ast(&self, program: &mut Program) -> Result<(), Diagnostic> {
+        let Some(check_return) = self.vtable.check_return else {
+            return Ok(());
+        };
+
+        for stmt in &program.statements {
+            // Only constant exit codes are known at compile time.
+            let Stmt::Return { value: Expr::Number { value, .. }, span } = stmt else {
+                continue;
+            };
+            let message = check_return(*value, span.start);
+            if !message.is_null() {
+                let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+                return Err(Diagnostic::new(
+                    diagnostics::E_PLUGIN,
+                    format!("{} (reported by plugin '{}')", message, self.name),
+                )
+                .at(*span));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_asm(&self, asm_code: String) -> String {
+        let Some(transform_asm) = self.vtable.transform_asm else {
+            return asm_code;
+        };
+        let Ok(c_asm) = CString::new(asm_code.as_str()) else {
+            return asm_code;
+        };
+
+        let result = transform_asm(c_asm.as_ptr());
+        if result.is_null() {
+            return asm_code;
+        }
+
+        unsafe {
+            let transformed = CStr::from_ptr(result).to_string_lossy().into_owned();
+            free(result as *mut c_void);
+            transformed
+        }
+    }
+}
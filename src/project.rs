@@ -0,0 +1,263 @@
+use std::fs;
+use std::path::Path;
+
+/// The manifest at the root of every project.
+pub const MANIFEST: &str = "ria.toml";
+
+/// The program a new project starts with, relative to its root.
+pub const MAIN_FILE: &str = "src/main.ria";
+
+/// Build outputs land next to where the compiler runs, so from the project
+/// root that is the executable named after `src/main.ria` plus its
+/// intermediate files. `.ria` caches fetched dependencies.
+const GITIGNORE: &str = "/.ria\n/main\n/libmain.a\n/libmain.so\n*.asm\n*.s\n*.o\n*.riac\n*.cov\n*.dot\n";
+
+/// A `name = { git = "url", rev = "..." }` entry under `[dependencies]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub git: String,
+    /// A commit, tag or branch to check out; the default branch if unset.
+    pub rev: Option<String>,
+}
+
+/// The contents of `ria.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub name: String,
+    /// The program to build, relative to the project root.
+    pub main: String,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Reads the string at the start of `input`, with the `\"`, `\\`, `\n`
+/// and `\t` escapes of a TOML basic string, and returns it with the rest
+/// of `input`.
+fn parse_string(input: &str) -> Option<(String, &str)> {
+    let body = input.strip_prefix('"')?;
+    let mut chars = body.char_indices();
+    let mut text = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((text, &body[i + 1..])),
+            '\\' => text.push(match chars.next()?.1 {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                't' => '\t',
+                _ => return None,
+            }),
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// Reads `value` as a quoted string, undoing its escapes.
+pub(crate) fn quoted(value: &str) -> Option<String> {
+    let (text, rest) = parse_string(value.trim())?;
+    rest.trim().is_empty().then_some(text)
+}
+
+/// Quotes `value` so that `quoted` reads it back unchanged.
+pub(crate) fn escape(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Splits `{ key = "value", ... }` into its pairs. Values may contain `,`
+/// and `}` since they are read as quoted strings.
+pub(crate) fn inline_table(value: &str) -> Option<Vec<(String, String)>> {
+    let mut rest = value.trim().strip_prefix('{')?.trim_start();
+    let mut pairs = Vec::new();
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            return after.trim().is_empty().then_some(pairs);
+        }
+        let (key, after) = rest.split_once('=')?;
+        let (value, after) = parse_string(after.trim_start())?;
+        pairs.push((key.trim().to_string(), value));
+        rest = after.trim_start();
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after.trim_start(),
+            None if rest.starts_with('}') => {}
+            None => return None,
+        }
+    }
+}
+
+/// Reads `entry = { git = "url", rev = "..." }` as a dependency.
+pub(crate) fn parse_dependency(name: &str, value: &str) -> Option<Dependency> {
+    let fields = inline_table(value)?;
+    let field = |key| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    Some(Dependency { name: name.to_string(), git: field("git")?, rev: field("rev") })
+}
+
+/// Rejects a URL or revision that git would take for an option, which a
+/// hostile manifest or lockfile could use to run commands.
+pub(crate) fn check_git_argument(key: &str, value: &str) -> Result<(), String> {
+    if value.starts_with('-') {
+        return Err(format!("'{}' cannot start with '-', got '{}'", key, value));
+    }
+    Ok(())
+}
+
+/// Calls `entry(section, key, value, line)` for every `key = value` line of
+/// the small TOML subset manifests and lockfiles use: `[section]` headers,
+/// `key = value` lines and `#` comments.
+pub(crate) fn parse_entries(
+    text: &str,
+    file: &str,
+    mut entry: impl FnMut(&str, &str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut section = String::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = header.trim().to_string();
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{} line {}: expected 'key = value'", file, number + 1))?;
+        entry(&section, key.trim(), value.trim())
+            .map_err(|e| format!("{} line {}: {}", file, number + 1, e))?;
+    }
+
+    Ok(())
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut main = None;
+        let mut dependencies = Vec::new();
+
+        parse_entries(text, MANIFEST, |section, key, value| {
+            match (section, key) {
+                ("package", "name" | "main") => {
+                    let value = quoted(value).ok_or("expected a quoted string")?;
+                    if key == "name" {
+                        name = Some(value);
+                    } else {
+                        main = Some(value);
+                    }
+                }
+                ("dependencies", _) => {
+                    check_name(key)?;
+                    let dependency = parse_dependency(key, value)
+                        .ok_or("expected '{ git = \"url\" }' with an optional 'rev'")?;
+                    check_git_argument("git", &dependency.git)?;
+                    if let Some(rev) = &dependency.rev {
+                        check_git_argument("rev", rev)?;
+                    }
+                    dependencies.push(dependency);
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+
+        Ok(Manifest {
+            name: name.ok_or_else(|| format!("{} has no [package] name", MANIFEST))?,
+            main: main.unwrap_or_else(|| MAIN_FILE.to_string()),
+            dependencies,
+        })
+    }
+
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let path = root.join(MANIFEST);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Error reading '{}': {}", path.display(), e))?;
+        Manifest::parse(&text)
+    }
+
+    /// The name build outputs share: the stem of the main file.
+    pub fn output_name(&self) -> &str {
+        Path::new(&self.main).file_stem().and_then(|stem| stem.to_str()).unwrap_or("output")
+    }
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if name.is_empty() || !valid || name.starts_with('-') {
+        return Err(format!(
+            "Invalid name '{}': use letters, digits, '_' and '-'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Creates the directory `name` holding a manifest, a `src/main.ria` that
+/// exits with 0 and a `.gitignore` for the build outputs.
+pub fn create(name: &str) -> Result<(), String> {
+    check_name(name)?;
+    let root = Path::new(name);
+    if root.exists() {
+        return Err(format!("'{}' already exists", name));
+    }
+
+    let manifest = format!("[package]\nname = \"{}\"\nmain = \"{}\"\n", name, MAIN_FILE);
+    let files = [
+        (MANIFEST, manifest.as_str()),
+        (MAIN_FILE, "kharrej 0;\n"),
+        (".gitignore", GITIGNORE),
+    ];
+
+    fs::create_dir_all(root.join("src"))
+        .map_err(|e| format!("Error creating '{}': {}", name, e))?;
+    for (file, content) in files {
+        let path = root.join(file);
+        fs::write(&path, content)
+            .map_err(|e| format!("Error writing '{}': {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Removes what building the project's main file from the root can leave
+/// behind: the executable or library, assembly, object files, bytecode,
+/// coverage counters and CFG dumps. Returns the files that were removed.
+pub fn clean(root: &Path) -> Result<Vec<String>, String> {
+    let manifest = Manifest::load(root)?;
+    let name = manifest.output_name();
+    let artifacts = [
+        name.to_string(),
+        format!("lib{}.a", name),
+        format!("lib{}.so", name),
+        format!("{}.asm", name),
+        format!("{}.s", name),
+        format!("{}.o", name),
+        format!("{}.riac", name),
+        format!("{}.cov", name),
+        format!("{}.dot", name),
+    ];
+
+    let mut removed = Vec::new();
+    for artifact in artifacts {
+        let path = root.join(&artifact);
+        if !path.is_file() {
+            continue;
+        }
+        fs::remove_file(&path)
+            .map_err(|e| format!("Error removing '{}': {}", path.display(), e))?;
+        removed.push(artifact);
+    }
+    Ok(removed)
+}
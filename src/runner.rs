@@ -0,0 +1,76 @@
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const RLIMIT_CPU: i32 = 0;
+const RLIMIT_CORE: i32 = 4;
+const SIGKILL: i32 = 9;
+
+/// How often `run` checks whether a program with a timeout has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[repr(C)]
+struct Rlimit {
+    current: u64,
+    maximum: u64,
+}
+
+unsafe extern "C" {
+    fn setrlimit(resource: i32, limit: *const Rlimit) -> i32;
+    fn kill(pid: i32, signal: i32) -> i32;
+}
+
+/// What became of a program `run` started.
+#[derive(Debug)]
+pub enum Outcome {
+    Exited(ExitStatus),
+    /// The program ran past its timeout and was killed.
+    TimedOut,
+}
+
+/// Runs `program` with `args` and the compiler's stdio and waits for it.
+/// With a `timeout`, the program and everything it starts (e.g. with
+/// `chghel`) run in their own process group, which is killed once the
+/// timeout passes. Each of those processes also inherits a CPU time limit of
+/// the same length, which still applies if the compiler itself is killed
+/// first; the limit counts every process on its own, not the group's total.
+/// None of them writes core dumps.
+pub fn run(program: &str, args: &[String], timeout: Option<Duration>) -> io::Result<Outcome> {
+    let mut command = Command::new(program);
+    command.args(args);
+    let Some(timeout) = timeout else {
+        return command.status().map(Outcome::Exited);
+    };
+
+    let cpu_seconds = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+    command.process_group(0);
+    // Safety: the closure runs between fork and exec, where only
+    // async-signal-safe calls are allowed; setrlimit is one.
+    unsafe {
+        command.pre_exec(move || {
+            let cpu = Rlimit { current: cpu_seconds, maximum: cpu_seconds };
+            let core = Rlimit { current: 0, maximum: 0 };
+            if setrlimit(RLIMIT_CPU, &cpu) != 0 || setrlimit(RLIMIT_CORE, &core) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn()?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Outcome::Exited(status));
+        }
+        if Instant::now() >= deadline {
+            // The group shares the child's pid; a negative pid signals it all.
+            unsafe { kill(-(child.id() as i32), SIGKILL) };
+            child.wait()?;
+            return Ok(Outcome::TimedOut);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::diagnostics::{self, Diagnostic};
+
+/// The byte order mark some editors put at the start of UTF-8 files.
+const BOM: &str = "\u{feff}";
+
+/// Turns the contents of the source file `file` into text, dropping a
+/// leading byte order mark. Offsets in later diagnostics count from after
+/// the mark.
+pub fn decode(bytes: Vec<u8>, file: &str) -> Result<String, Diagnostic> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(match text.strip_prefix(BOM) {
+            Some(rest) => rest.to_string(),
+            None => text,
+        }),
+        Err(e) => {
+            let offset = e.utf8_error().valid_up_to();
+            Err(Diagnostic::new(
+                diagnostics::E_INVALID_UTF8,
+                format!(
+                    "'{}' is not valid UTF-8: unexpected byte 0x{:02x} at byte {}",
+                    file,
+                    e.as_bytes()[offset],
+                    offset
+                ),
+            ))
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::json;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator to keep track of the peak number of live heap
+/// bytes, which `--stats` reports.
+pub struct CountingAllocator;
+
+fn record_growth(bytes: usize) {
+    let now = CURRENT.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            record_growth(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                record_growth(new_size - layout.size());
+            } else {
+                CURRENT.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+pub fn peak_heap_bytes() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Default)]
+pub struct CompileStats {
+    pub source_bytes: usize,
+    pub tokens: usize,
+    pub ast_nodes: usize,
+    pub asm_instructions: usize,
+    pub asm_bytes: usize,
+}
+
+impl CompileStats {
+    pub fn to_json(&self) -> String {
+        let fields = [
+            ("source_bytes", self.source_bytes),
+            ("tokens", self.tokens),
+            ("ast_nodes", self.ast_nodes),
+            ("asm_instructions", self.asm_instructions),
+            ("asm_bytes", self.asm_bytes),
+            ("peak_heap_bytes", peak_heap_bytes()),
+        ];
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(name, value)| format!("  {}: {}", json::string(name), value))
+            .collect();
+        format!("{{\n{}\n}}", body.join(",\n"))
+    }
+}
+
+/// Counts instruction lines in generated assembly, skipping labels,
+/// directives, blank lines and comment-only lines.
+pub fn count_instructions(asm_code: &str) -> usize {
+    asm_code
+        .lines()
+        .filter(|line| line.starts_with([' ', '\t']))
+        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with(';'))
+        .count()
+}
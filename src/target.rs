@@ -0,0 +1,161 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arch {
+    X86_64,
+    Aarch64,
+    Riscv64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Os {
+    Linux,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectFormat {
+    Elf64,
+}
+
+/// What the compiler produces code for: the instruction set, the operating
+/// system whose syscalls the program uses, and the object file format the
+/// assembler writes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Target {
+    pub arch: Arch,
+    pub os: Os,
+    pub format: ObjectFormat,
+}
+
+pub const SUPPORTED_TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "riscv64gc-unknown-linux-gnu",
+];
+
+impl Arch {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Riscv64 => "riscv64",
+        }
+    }
+
+    fn host() -> Arch {
+        if cfg!(target_arch = "aarch64") {
+            Arch::Aarch64
+        } else if cfg!(target_arch = "riscv64") {
+            Arch::Riscv64
+        } else {
+            Arch::X86_64
+        }
+    }
+}
+
+impl Os {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Os::Linux => "linux",
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Target::host()
+    }
+}
+
+impl Target {
+    pub fn host() -> Self {
+        Target {
+            arch: Arch::host(),
+            os: Os::Linux,
+            format: ObjectFormat::Elf64,
+        }
+    }
+
+    /// Parses a target triple such as `aarch64-unknown-linux-gnu`. The vendor
+    /// and environment parts are accepted but not interpreted.
+    pub fn parse(triple: &str) -> Result<Self, String> {
+        let mut parts = triple.split('-');
+
+        let arch = match parts.next() {
+            Some("x86_64" | "amd64") => Arch::X86_64,
+            Some("aarch64" | "arm64") => Arch::Aarch64,
+            Some("riscv64" | "riscv64gc") => Arch::Riscv64,
+            _ => return Err(unsupported(triple)),
+        };
+
+        if !parts.any(|part| part == "linux") {
+            return Err(unsupported(triple));
+        }
+
+        Ok(Target {
+            arch,
+            os: Os::Linux,
+            format: ObjectFormat::Elf64,
+        })
+    }
+
+    pub fn is_host(&self) -> bool {
+        *self == Target::host()
+    }
+
+    /// Prefix of the binutils that handle this target, e.g.
+    /// `aarch64-linux-gnu-` when cross-compiling; empty for the host.
+    pub fn tool_prefix(&self) -> String {
+        if self.is_host() {
+            String::new()
+        } else {
+            format!("{}-linux-gnu-", self.arch.name())
+        }
+    }
+
+    /// The assembler command and the flags it needs before the input file.
+    pub fn assembler(&self) -> (String, Vec<&'static str>) {
+        match (self.arch, self.format) {
+            (Arch::X86_64, ObjectFormat::Elf64) => ("nasm".to_string(), vec!["-f", "elf64"]),
+            (Arch::Aarch64 | Arch::Riscv64, ObjectFormat::Elf64) => {
+                (format!("{}as", self.tool_prefix()), Vec::new())
+            }
+        }
+    }
+
+    pub fn linker(&self) -> String {
+        format!("{}ld", self.tool_prefix())
+    }
+
+    pub fn asm_extension(&self) -> &'static str {
+        match self.arch {
+            Arch::X86_64 => "asm",
+            Arch::Aarch64 | Arch::Riscv64 => "s",
+        }
+    }
+
+    pub fn dynamic_linker(&self) -> &'static str {
+        match (self.arch, self.os) {
+            (Arch::X86_64, Os::Linux) => "/lib64/ld-linux-x86-64.so.2",
+            (Arch::Aarch64, Os::Linux) => "/lib/ld-linux-aarch64.so.1",
+            (Arch::Riscv64, Os::Linux) => "/lib/ld-linux-riscv64-lp64d.so.1",
+        }
+    }
+}
+
+fn unsupported(triple: &str) -> String {
+    format!(
+        "Unsupported target '{}'. Supported targets: {}",
+        triple,
+        SUPPORTED_TARGETS.join(", ")
+    )
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let os = match self.os {
+            Os::Linux => "linux-gnu",
+        };
+        write!(f, "{}-unknown-{}", self.arch.name(), os)
+    }
+}
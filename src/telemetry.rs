@@ -0,0 +1,115 @@
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::json;
+
+/// What `--stats-json` has gathered about the current build so far.
+struct Report {
+    path: String,
+    input: String,
+    started: Instant,
+    passes: Vec<(&'static str, Duration)>,
+    errors: Vec<&'static str>,
+    warnings: usize,
+    artifacts: Vec<String>,
+}
+
+/// Global so that every exit path can finish the report, including the
+/// `process::exit` calls that skip destructors.
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+fn with_report(f: impl FnOnce(&mut Report)) {
+    if let Some(report) = REPORT.lock().unwrap().as_mut() {
+        f(report);
+    }
+}
+
+/// Starts collecting a report about building `input`, to be written to
+/// `path` by `finish`. Until this is called, recording is a no-op.
+pub fn enable(path: &str, input: &str) {
+    *REPORT.lock().unwrap() = Some(Report {
+        path: path.to_string(),
+        input: input.to_string(),
+        started: Instant::now(),
+        passes: Vec::new(),
+        errors: Vec::new(),
+        warnings: 0,
+        artifacts: Vec::new(),
+    });
+}
+
+pub fn record_pass(pass: &'static str, duration: Duration) {
+    with_report(|report| report.passes.push((pass, duration)));
+}
+
+/// Records an error diagnostic by its code.
+pub fn record_error(code: &'static str) {
+    with_report(|report| report.errors.push(code));
+}
+
+pub fn record_warning() {
+    with_report(|report| report.warnings += 1);
+}
+
+/// Records a file the build wrote; its size is read when the report is.
+pub fn record_artifact(path: &str) {
+    with_report(|report| report.artifacts.push(path.to_string()));
+}
+
+impl Report {
+    fn to_json(&self, success: bool) -> String {
+        let passes: Vec<String> = self
+            .passes
+            .iter()
+            .map(|(pass, duration)| {
+                format!(
+                    "    {{\"name\": {}, \"seconds\": {:.6}}}",
+                    json::string(pass),
+                    duration.as_secs_f64()
+                )
+            })
+            .collect();
+        let codes: Vec<String> = self.errors.iter().map(|code| json::string(code)).collect();
+        let artifacts: Vec<String> = self
+            .artifacts
+            .iter()
+            .map(|path| {
+                let bytes = fs::metadata(path).map_or(0, |metadata| metadata.len());
+                format!("    {{\"path\": {}, \"bytes\": {}}}", json::string(path), bytes)
+            })
+            .collect();
+        let list = |items: &[String]| {
+            if items.is_empty() {
+                "[]".to_string()
+            } else {
+                format!("[\n{}\n  ]", items.join(",\n"))
+            }
+        };
+
+        format!(
+            "{{\n  \"input\": {},\n  \"success\": {},\n  \"total_seconds\": {:.6},\n  \
+             \"passes\": {},\n  \"diagnostics\": {{\"errors\": {}, \"warnings\": {}, \
+             \"codes\": [{}]}},\n  \"artifacts\": {}\n}}\n",
+            json::string(&self.input),
+            success,
+            self.started.elapsed().as_secs_f64(),
+            list(&passes),
+            self.errors.len(),
+            self.warnings,
+            codes.join(", "),
+            list(&artifacts)
+        )
+    }
+}
+
+/// Writes the report, if one was started, and stops collecting. Failing to
+/// write it is only a warning, since the build itself is already decided.
+pub fn finish(success: bool) {
+    let Some(report) = REPORT.lock().unwrap().take() else {
+        return;
+    };
+    if let Err(e) = fs::write(&report.path, report.to_json(success)) {
+        eprintln!("Warning: could not write '{}': {}", report.path, e);
+    }
+}
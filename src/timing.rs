@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+use crate::telemetry;
+
+/// Collects how long each compiler pass took and prints the table to stderr
+/// when dropped, so early exits such as `--emit` still get a report.
+pub struct PassTimer {
+    enabled: bool,
+    started: Instant,
+    passes: Vec<(&'static str, Duration)>,
+}
+
+impl PassTimer {
+    pub fn new(enabled: bool) -> Self {
+        PassTimer {
+            enabled,
+            started: Instant::now(),
+            passes: Vec::new(),
+        }
+    }
+
+    pub fn time<T>(&mut self, pass: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        let duration = start.elapsed();
+        self.passes.push((pass, duration));
+        telemetry::record_pass(pass, duration);
+        result
+    }
+}
+
+impl Drop for PassTimer {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        for (pass, duration) in &self.passes {
+            eprintln!("time: {:>10.6}s   {}", duration.as_secs_f64(), pass);
+        }
+        eprintln!("time: {:>10.6}s   total", self.started.elapsed().as_secs_f64());
+    }
+}
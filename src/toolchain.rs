@@ -0,0 +1,265 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::target::Target;
+use crate::telemetry;
+
+/// Why assembling, linking or archiving failed.
+#[derive(Debug)]
+pub enum ToolchainError {
+    /// An intermediate file could not be written.
+    Write { path: String, error: io::Error },
+    /// The tool could not be started, usually because it is not installed.
+    Spawn { tool: String, error: io::Error },
+    /// The tool ran and reported an error on stderr.
+    Failed { tool: String, stderr: String },
+}
+
+impl fmt::Display for ToolchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolchainError::Write { path, error } => {
+                write!(f, "could not write '{}': {}", path, error)
+            }
+            ToolchainError::Spawn { tool, error } => write!(f, "could not run {}: {}", tool, error),
+            ToolchainError::Failed { tool, stderr } => write!(f, "{} failed: {}", tool, stderr),
+        }
+    }
+}
+
+impl std::error::Error for ToolchainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ToolchainError::Write { error, .. } | ToolchainError::Spawn { error, .. } => {
+                Some(error)
+            }
+            ToolchainError::Failed { .. } => None,
+        }
+    }
+}
+
+/// Runs `command` to completion, turning a failure to start it or a
+/// failing exit status into a `ToolchainError`.
+fn run(mut command: Command) -> Result<(), ToolchainError> {
+    let tool = command.get_program().to_string_lossy().into_owned();
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(error) => return Err(ToolchainError::Spawn { tool, error }),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        return Err(ToolchainError::Failed { tool, stderr });
+    }
+    Ok(())
+}
+
+/// Where a bundled install keeps its own tools: `libexec/ria` under the
+/// prefix whose `bin` holds the compiler.
+pub fn bundled_dir() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    Some(exe.parent()?.parent()?.join("libexec").join("ria"))
+}
+
+/// The program to run for `tool`: the bundled copy if there is one, so the
+/// compiler works on machines without the tools installed, and otherwise
+/// the bare name for a `PATH` lookup.
+pub fn resolve(tool: &str) -> OsString {
+    match bundled_dir().map(|dir| dir.join(tool)) {
+        Some(path) if path.is_file() => path.into_os_string(),
+        _ => OsString::from(tool),
+    }
+}
+
+/// Finds the file `resolve(tool)` runs, searching `PATH` like the OS does.
+pub fn locate(tool: &str) -> Option<PathBuf> {
+    let resolved = PathBuf::from(resolve(tool));
+    if resolved.is_absolute() {
+        return Some(resolved);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(tool))
+        .find(|path| path.is_file())
+}
+
+/// The tools building for `target` runs.
+pub fn required_tools(target: &Target) -> Vec<String> {
+    let (assembler, _) = target.assembler();
+    vec![assembler, target.linker(), "ar".to_string()]
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LinkMode {
+    #[default]
+    Static,
+    /// Request the system dynamic loader through PT_INTERP. ld only emits the
+    /// interpreter when the output is PIE or pulls in a shared library.
+    Dynamic,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    pub pie: bool,
+    pub mode: LinkMode,
+    pub strip: bool,
+    /// Extra object files and archives linked in after the program itself.
+    pub objects: Vec<String>,
+    pub search_paths: Vec<String>,
+    pub libraries: Vec<String>,
+    pub extra_args: Vec<String>,
+}
+
+/// The linker invocation `link` runs.
+pub fn link_command(
+    obj_file: &str,
+    output_name: &str,
+    link_options: &LinkOptions,
+    target: &Target,
+) -> Command {
+    let mut ld = Command::new(resolve(&target.linker()));
+    match (link_options.mode, link_options.pie) {
+        (LinkMode::Static, false) => {
+            ld.arg("-static");
+        }
+        (LinkMode::Static, true) => {
+            ld.args(["-pie", "--no-dynamic-linker", "-z", "text"]);
+        }
+        (LinkMode::Dynamic, pie) => {
+            if pie {
+                ld.arg("-pie");
+            }
+            ld.args(["--dynamic-linker", target.dynamic_linker()]);
+        }
+    }
+    if link_options.strip {
+        ld.arg("-s");
+    }
+    // Keep output byte-for-byte reproducible regardless of the ld default.
+    ld.arg("--build-id=none");
+    ld.args([obj_file, "-o", output_name]);
+    ld.args(&link_options.objects);
+    for path in &link_options.search_paths {
+        ld.arg(format!("-L{}", path));
+    }
+    for library in &link_options.libraries {
+        ld.arg(format!("-l{}", library));
+    }
+    ld.args(&link_options.extra_args);
+    ld
+}
+
+pub fn link(
+    obj_file: &str,
+    output_name: &str,
+    link_options: &LinkOptions,
+    target: &Target,
+) -> Result<(), ToolchainError> {
+    println!("Linking with {}...", target.linker());
+    run(link_command(obj_file, output_name, link_options, target))?;
+    println!("Linked to executable: {}", output_name);
+    telemetry::record_artifact(output_name);
+    
+    // let _ = fs::remove_file(asm_file);
+    // let _ = fs::remove_file(obj_file);
+    
+    Ok(())
+}
+
+/// The linker invocation `link_shared` runs to create `lib_file`. Only the
+/// program's entry is global in its object, so that is all the library
+/// exports; `--exclude-libs` keeps the symbols of archives linked into it
+/// from being exported too.
+pub fn link_shared_command(
+    obj_file: &str,
+    lib_file: &str,
+    link_options: &LinkOptions,
+    target: &Target,
+) -> Command {
+    let mut ld = Command::new(resolve(&target.linker()));
+    ld.args(["-shared", "--exclude-libs", "ALL", "-z", "text"]);
+    if link_options.strip {
+        ld.arg("-s");
+    }
+    ld.arg("--build-id=none");
+    ld.args([obj_file, "-o", lib_file]);
+    ld.args(&link_options.objects);
+    for path in &link_options.search_paths {
+        ld.arg(format!("-L{}", path));
+    }
+    for library in &link_options.libraries {
+        ld.arg(format!("-l{}", library));
+    }
+    ld.args(&link_options.extra_args);
+    ld
+}
+
+pub fn link_shared(
+    obj_file: &str,
+    output_name: &str,
+    link_options: &LinkOptions,
+    target: &Target,
+) -> Result<String, ToolchainError> {
+    let lib_file = format!("lib{}.so", output_name);
+
+    println!("Linking with {}...", target.linker());
+    run(link_shared_command(obj_file, &lib_file, link_options, target))?;
+    println!("Linked to shared library: {}", lib_file);
+    telemetry::record_artifact(&lib_file);
+
+    Ok(lib_file)
+}
+
+/// The `ar` invocation `archive` runs to create `lib_file`.
+pub fn archive_command(obj_file: &str, lib_file: &str) -> Command {
+    let mut ar = Command::new(resolve("ar"));
+    // `D` zeroes member timestamps, uids and modes so the archive is reproducible.
+    ar.args(["rcsD", lib_file, obj_file]);
+    ar
+}
+
+pub fn archive(obj_file: &str, output_name: &str) -> Result<String, ToolchainError> {
+    let lib_file = format!("lib{}.a", output_name);
+
+    println!("Archiving with ar...");
+    let _ = fs::remove_file(&lib_file);
+    run(archive_command(obj_file, &lib_file))?;
+    println!("Archived to static library: {}", lib_file);
+    telemetry::record_artifact(&lib_file);
+
+    Ok(lib_file)
+}
+
+/// The assembler invocation `assemble` runs.
+pub fn assemble_command(asm_file: &str, obj_file: &str, target: &Target) -> Command {
+    let (assembler, flags) = target.assembler();
+    let mut command = Command::new(resolve(&assembler));
+    command.args(flags).args([asm_file, "-o", obj_file]);
+    command
+}
+
+pub fn assemble(
+    asm_code: &str,
+    output_name: &str,
+    target: &Target,
+) -> Result<String, ToolchainError> {
+    let asm_file = format!("{}.{}", output_name, target.asm_extension());
+    let obj_file = format!("{}.o", output_name);
+    
+    if let Err(error) = fs::write(&asm_file, asm_code) {
+        return Err(ToolchainError::Write { path: asm_file, error });
+    }
+    println!("Generated assembly written to {}", asm_file);
+    telemetry::record_artifact(&asm_file);
+    
+    let (assembler, _) = target.assembler();
+    println!("Assembling with {}...", assembler);
+    run(assemble_command(&asm_file, &obj_file, target))?;
+    println!("Assembled to object file: {}", obj_file);
+    telemetry::record_artifact(&obj_file);
+
+    Ok(obj_file)
+}
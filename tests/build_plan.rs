@@ -0,0 +1,48 @@
+mod common;
+
+use common::{Scratch, zakaria};
+
+#[test]
+fn build_plan_lists_steps_without_building() {
+    let dir = Scratch::new("build-plan");
+    dir.write("prog.ria", "kharrej 3;\n");
+
+    let output = zakaria(&dir, &["build", "prog.ria", "--build-plan", "--strip"]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+
+    assert!(plan.contains("\"file\": \"prog.ria\""), "{}", plan);
+    assert!(plan.contains("\"kind\": \"assemble\""), "{}", plan);
+    assert!(plan.contains("\"kind\": \"link\""), "{}", plan);
+    assert!(plan.contains("\"-s\""), "{}", plan);
+    assert!(plan.contains("\"artifacts\": [\"prog.asm\", \"prog.o\", \"prog\"]"), "{}", plan);
+    assert!(!plan.contains("--build-plan"), "{}", plan);
+    assert!(!dir.join("prog.asm").exists());
+}
+
+#[test]
+fn compile_only_plan_stops_at_the_object_file() {
+    let dir = Scratch::new("build-plan-c");
+    dir.write("prog.ria", "kharrej 3;\n");
+
+    let output = zakaria(&dir, &["build", "-c", "prog.ria", "--build-plan"]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+
+    assert!(plan.contains("\"output\": \"prog.o\""), "{}", plan);
+    assert!(!plan.contains("\"kind\": \"link\""), "{}", plan);
+    assert!(plan.contains("\"artifacts\": [\"prog.asm\", \"prog.o\"]"), "{}", plan);
+}
+
+#[test]
+fn cdylib_plan_links_a_shared_library() {
+    let dir = Scratch::new("build-plan-so");
+    dir.write("prog.ria", "kharrej 3;\n");
+
+    let output = zakaria(&dir, &["build", "prog.ria", "--crate-type", "cdylib", "--build-plan"]);
+    assert!(output.status.success());
+    let plan = String::from_utf8(output.stdout).unwrap();
+
+    assert!(plan.contains("\"output\": \"libprog.so\""), "{}", plan);
+    assert!(plan.contains("\"ld\", \"-shared\""), "{}", plan);
+}
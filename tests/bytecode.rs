@@ -0,0 +1,47 @@
+mod common;
+
+use ria::cli::Emit;
+use ria::{Artifact, Compiler, bytecode};
+
+use common::{Scratch, command, zakaria};
+
+#[test]
+fn emitted_bytecode_runs_in_the_vm() {
+    let dir = Scratch::new("bytecode");
+    dir.write("prog.ria", "kharrej 42;\nkharrej 7;\n");
+
+    assert!(zakaria(&dir, &["build", "prog.ria", "--emit", "bytecode"]).status.success());
+    assert_eq!(zakaria(&dir, &["run", "prog.riac"]).status.code(), Some(42));
+}
+
+#[test]
+fn vm_makes_host_syscalls() {
+    let dir = Scratch::new("bytecode-syscall");
+    // 39 is getpid on x86_64; the exit code is the low byte of the VM's pid.
+    dir.write("prog.ria", "kharrej syscall(39);\n");
+
+    assert!(zakaria(&dir, &["build", "prog.ria", "--emit", "bytecode"]).status.success());
+
+    let mut vm = command(&dir).args(["run", "prog.riac"]).spawn().unwrap();
+    let pid = vm.id();
+    let status = vm.wait().unwrap();
+    if cfg!(target_arch = "x86_64") {
+        assert_eq!(status.code(), Some((pid & 0xff) as i32));
+    }
+}
+
+#[test]
+fn program_without_kharrej_exits_with_zero() {
+    let Ok(Artifact::Ast(program)) = Compiler::new().source("").emit(Emit::Ast).compile() else {
+        panic!("an empty program should compile");
+    };
+    assert!(program.statements.is_empty());
+    assert_eq!(bytecode::run(&bytecode::compile(&program)), Ok(0));
+}
+
+#[test]
+fn long_spawn_paths_round_trip() {
+    let path = "x".repeat(70_000);
+    let code = [bytecode::Instr::Spawn(path), bytecode::Instr::Ret];
+    assert_eq!(bytecode::decode(&bytecode::encode(&code)).unwrap(), code);
+}
@@ -0,0 +1,63 @@
+use ria::cli::{self, Invocation};
+
+fn parse(args: &[&str]) -> Result<Invocation, String> {
+    cli::parse_args(std::iter::once("zakaria").chain(args.iter().copied()).map(String::from))
+}
+
+#[test]
+fn link_collects_objects_and_linker_flags() {
+    let Ok(Invocation::Link(job)) = parse(&["link", "main.o", "libutil.a", "-o", "prog", "-lc"])
+    else {
+        panic!("expected a link invocation");
+    };
+    assert_eq!(job.objects, ["main.o", "libutil.a"]);
+    assert_eq!(job.output, "prog");
+    assert_eq!(job.link.libraries, ["c"]);
+
+    assert!(parse(&["link", "main.ria"]).is_err());
+    assert!(parse(&["link", "-o", "prog"]).is_err());
+}
+
+#[test]
+fn compile_only_is_for_builds_of_one_program() {
+    let Ok(Invocation::Compile(options)) = parse(&["build", "-c", "main.ria"]) else {
+        panic!("expected a compile invocation");
+    };
+    assert!(options.compile_only);
+    assert!(!options.run);
+
+    assert!(parse(&["run", "-c", "main.ria"]).is_err());
+    assert!(parse(&["build", "-c", "main.ria", "extra.o"]).is_err());
+}
+
+#[test]
+fn arguments_after_double_dash_go_to_the_program() {
+    let Ok(Invocation::Compile(options)) = parse(&["run", "main.ria", "--", "-O2", "x.o"]) else {
+        panic!("expected a compile invocation");
+    };
+    assert_eq!(options.program_args, ["-O2", "x.o"]);
+    assert!(options.link.objects.is_empty());
+
+    let Ok(Invocation::Compile(options)) = parse(&["run", "main.ria", "--"]) else {
+        panic!("expected a compile invocation");
+    };
+    assert!(options.program_args.is_empty());
+
+    assert!(parse(&["build", "main.ria", "--", "a"]).is_err());
+    assert!(parse(&["run", "main.ria", "--jit", "--", "a"]).is_err());
+}
+
+#[test]
+fn coverage_reports_take_the_build_flags() {
+    let args = ["cov", "report", "main.ria", "-Ilib", "--cfg", "DEMO", "--target"];
+    let triple = "riscv64gc-unknown-linux-gnu";
+    let Ok(Invocation::CoverageReport(options)) = parse(&[&args[..], &[triple]].concat()) else {
+        panic!("expected a coverage report invocation");
+    };
+    assert_eq!(options.include_paths, ["lib"]);
+    assert_eq!(options.cfg_flags, ["DEMO"]);
+    assert_eq!(options.target.arch.name(), "riscv64");
+
+    assert!(parse(&["cov", "main.ria"]).is_err());
+    assert!(parse(&["cov", "report", "main.ria", "--", "x"]).is_err());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use ria::cli::Emit;
+use ria::target::Target;
+use ria::{Artifact, Compiler, Output};
+
+use common::{Scratch, zakaria};
+
+/// A path with a comment marker, a backslash, a backquote, a carriage
+/// return and non-ASCII text, none of which may end the string operand.
+const SOURCE: &str = "kharrej chghel(\"/tmp/a;b\\ `\r\u{e9}\");";
+
+fn assembly(triple: &str) -> String {
+    let target = Target::parse(triple).unwrap();
+    let Ok(Artifact::Assembly(asm)) = Compiler::new().source(SOURCE).target(target).compile() else {
+        panic!("failed to compile for {}", triple);
+    };
+    asm
+}
+
+#[test]
+fn string_data_stays_inside_its_operand() {
+    let x86 = assembly("x86_64-unknown-linux-gnu");
+    assert!(x86.contains("ria_str0: db \"/tmp/a;b\\ `\", 13, 195, 169, 0\n"), "{}", x86);
+
+    let aarch64 = assembly("aarch64-unknown-linux-gnu");
+    assert!(aarch64.contains("ria_str0: .asciz \"/tmp/a;b\\\\ `\\015\\303\\251\"\n"), "{}", aarch64);
+
+    let riscv64 = assembly("riscv64gc-unknown-linux-gnu");
+    assert!(riscv64.contains("ria_str0: .asciz \"/tmp/a;b\\\\ `\\015\\303\\251\"\n"), "{}", riscv64);
+}
+
+#[test]
+fn command_line_output_matches_the_library() {
+    let dir = Scratch::new("codegen");
+    dir.write("prog.ria", SOURCE);
+
+    let output = zakaria(&dir, &["build", "prog.ria", "--emit", "llvm-ir"]);
+    let compiler = Compiler::new().source(SOURCE).name("prog.ria").emit(Emit::LlvmIr);
+    let Ok(Artifact::LlvmIr(ir)) = compiler.compile() else {
+        panic!("failed to compile");
+    };
+    assert_eq!(String::from_utf8_lossy(&output.stdout), ir);
+}
+
+#[test]
+fn llvm_source_filename_uses_hex_escapes() {
+    let name = "a\"b\\\n\u{e9}.ria";
+    let compiler = Compiler::new().source("kharrej 0;").name(name).emit(Emit::LlvmIr);
+    let Ok(Artifact::LlvmIr(ir)) = compiler.compile() else {
+        panic!("failed to compile");
+    };
+    assert!(ir.contains("source_filename = \"a\\22b\\5C\\0A\\C3\\A9.ria\"\n"), "{}", ir);
+    assert!(ir.starts_with("; ModuleID = 'a\\22b\\5C\\0A\\C3\\A9.ria'\n"), "{}", ir);
+}
+
+#[test]
+fn every_emit_kind_compiles_to_its_artifact() {
+    let emits = [Emit::Tokens, Emit::Ast, Emit::Cfg, Emit::LlvmIr, Emit::Bytecode];
+    for emit in emits {
+        let artifact = Compiler::new().source("kharrej 2;").emit(emit).compile().unwrap();
+        let matches = match emit {
+            Emit::Tokens => matches!(artifact, Artifact::Tokens(_)),
+            Emit::Ast => matches!(artifact, Artifact::Ast(_)),
+            Emit::Cfg => matches!(artifact, Artifact::Cfg(_)),
+            Emit::LlvmIr => matches!(artifact, Artifact::LlvmIr(_)),
+            Emit::Bytecode => matches!(artifact, Artifact::Bytecode(_)),
+        };
+        assert!(matches, "{:?} gave {:?}", emit, artifact);
+    }
+    assert_eq!(Output::of(Some(Emit::Tokens)), None);
+}
@@ -0,0 +1,64 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+/// An empty directory under the system temp dir, named after the test and
+/// this process, that is removed again when dropped.
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    pub fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("ria-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    /// Writes `file`, relative to the directory, creating its parents.
+    pub fn write(&self, file: &str, contents: impl AsRef<[u8]>) {
+        let path = self.0.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    pub fn read(&self, file: &str) -> String {
+        fs::read_to_string(self.0.join(file)).unwrap()
+    }
+}
+
+impl Deref for Scratch {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for Scratch {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The compiler binary, to be run in `dir`.
+pub fn command(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_MyCompiler"));
+    command.current_dir(dir);
+    command
+}
+
+/// Runs the compiler in `dir` with `args` and waits for it to finish.
+pub fn zakaria(dir: &Path, args: &[&str]) -> Output {
+    command(dir).args(args).output().unwrap()
+}
@@ -0,0 +1,28 @@
+use ria::cli::Emit;
+use ria::conditional::{self, Conditions};
+use ria::target::Target;
+use ria::{Artifact, Compiler};
+
+#[test]
+fn sections_follow_target_and_cfg_flags() {
+    let source = "#ila ARCH == \"riscv64\"\nkharrej 1;\n#wla\nkharrej 2;\n#sala\n#ila DEMO\nkharrej 3;\n#sala\n";
+    let riscv = Target::parse("riscv64gc-unknown-linux-gnu").unwrap();
+    let aarch64 = Target::parse("aarch64-unknown-linux-gnu").unwrap();
+
+    let kept = conditional::evaluate(source, &Conditions::new(&riscv, &["DEMO".to_string()])).unwrap();
+    assert_eq!(kept, "\nkharrej 1;\n\n\n\n\nkharrej 3;\n\n");
+
+    let kept = conditional::evaluate(source, &Conditions::new(&aarch64, &[])).unwrap();
+    assert_eq!(kept, "\n\n\nkharrej 2;\n\n\n\n\n");
+}
+
+#[test]
+fn compiler_evaluates_sections() {
+    let source = "#ila DEMO\nkharrej 1;\n#wla\nkharrej 2;\n#sala\n";
+    let ast = |compiler: Compiler| match compiler.source(source).emit(Emit::Ast).compile() {
+        Ok(Artifact::Ast(program)) => program.to_sexpr(),
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert!(ast(Compiler::new().cfg("DEMO")).contains("(number 1)"));
+    assert!(ast(Compiler::new()).contains("(number 2)"));
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::path::Path;
+
+use ria::config;
+
+use common::{Scratch, command};
+
+#[test]
+fn build_settings_become_flags() {
+    let text = "[package]\nname = \"demo\"\n\n[build]\ntarget = \"riscv64gc-unknown-linux-gnu\"\n\
+                opt-level = \"s\"\n";
+    let flags = config::parse(text, "ria.toml").unwrap();
+    assert_eq!(flags, ["--target", "riscv64gc-unknown-linux-gnu", "-Os"]);
+
+    let error = config::parse("[build]\ncolor = \"always\"\n", "ria.toml").unwrap_err();
+    assert!(error.contains("unknown build setting 'color'"), "{}", error);
+}
+
+fn planned_target(dir: &Path, args: &[&str]) -> String {
+    let output = command(dir)
+        .arg("build")
+        .args(args)
+        .args(["prog.ria", "--build-plan"])
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let plan = String::from_utf8(output.stdout).unwrap();
+    let (_, rest) = plan.split_once("\"target\": \"").unwrap();
+    rest.split('"').next().unwrap().to_string()
+}
+
+#[test]
+fn command_line_overrides_project_and_user_defaults() {
+    let dir = Scratch::new("config");
+    dir.write("prog.ria", "kharrej 3;\n");
+
+    let user = "[build]\ntarget = \"aarch64-unknown-linux-gnu\"\n";
+    dir.write("config/ria/config.toml", user);
+    assert_eq!(planned_target(&dir, &[]), "aarch64-unknown-linux-gnu");
+
+    let project = "[package]\nname = \"prog\"\n\n[build]\ntarget = \"riscv64gc-unknown-linux-gnu\"\n";
+    dir.write("ria.toml", project);
+    assert_eq!(planned_target(&dir, &[]), "riscv64-unknown-linux-gnu");
+
+    let flag = ["--target", "x86_64-unknown-linux-gnu"];
+    assert_eq!(planned_target(&dir, &flag), "x86_64-unknown-linux-gnu");
+}
+
+#[test]
+fn command_line_opt_level_turns_off_size_defaults() {
+    let dir = Scratch::new("config-opt-level");
+    dir.write("prog.ria", "kharrej 3;\n");
+    dir.write("ria.toml", "[package]\nname = \"prog\"\n\n[build]\nopt-level = \"s\"\n");
+    let build = |args: &[&str]| {
+        let output = command(&dir)
+            .arg("build")
+            .args(args)
+            .arg("prog.ria")
+            .env("XDG_CONFIG_HOME", dir.join("config"))
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(build(&["--build-plan"]).contains("\"-s\""));
+    assert!(!build(&["-O0", "--build-plan"]).contains("\"-s\""));
+
+    // Without -Os the assembly keeps its comments.
+    let asm = |args: &[&str]| {
+        let output = build(&[args, &["--force"]].concat());
+        output.split_once("Generated Assembly:").unwrap().1.to_string()
+    };
+    let compact = asm(&[]);
+    assert!(!compact.contains("; sys_exit"), "{}", compact);
+    let commented = asm(&["-O0"]);
+    assert!(commented.contains("; sys_exit"), "{}", commented);
+}
@@ -0,0 +1,18 @@
+mod common;
+
+use common::{Scratch, zakaria};
+
+#[test]
+fn report_sees_the_program_the_build_flags_select() {
+    let dir = Scratch::new("coverage");
+    dir.write("lib/defs.ria", "kharrej 1;\n");
+    dir.write("prog.ria", "#ila DEMO\njme3 \"defs.ria\";\n#sala\nkharrej 2;\n");
+    // Counters as an instrumented build writes them: one u64 per statement.
+    dir.write("prog.cov", [5u64, 7].map(u64::to_le_bytes).concat());
+
+    let output = zakaria(&dir, &["cov", "report", "prog.ria", "-I", "lib", "--cfg", "DEMO"]);
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(report.contains("5:    2: kharrej 1;\n"), "{}", report);
+    assert!(report.contains("7:    4: kharrej 2;\n"), "{}", report);
+}
@@ -0,0 +1,27 @@
+use ria::Compiler;
+use ria::diagnostics::explain;
+
+/// The indented lines after "Erroneous example:" in the explanation of `code`.
+fn erroneous_example(code: &str) -> String {
+    let text = explain(code).unwrap();
+    let (_, rest) = text.split_once("Erroneous example:\n\n").unwrap();
+    rest.lines()
+        .take_while(|line| line.starts_with("    "))
+        .map(|line| &line[4..])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn erroneous_examples_produce_their_own_error() {
+    // The others need files, flags or plugins to reproduce.
+    let codes = [
+        "E0001", "E0002", "E0003", "E0004", "E0005", "E0007", "E0009", "E0011", "E0012", "E0013",
+        "E0014", "E0015", "E0016", "E0017", "E0020",
+    ];
+    for code in codes {
+        let example = erroneous_example(code);
+        let error = Compiler::new().source(example.as_str()).compile().unwrap_err();
+        assert_eq!(error.code(), code, "{}", example);
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::process::Command;
+
+use ria::cfg::Cfg;
+use ria::cli::Emit;
+use ria::target::Target;
+use ria::{Artifact, Compiler, bytecode, jit, llvm};
+
+use common::Scratch;
+
+/// i64::MIN, built from literals since none can be that large.
+const MIN: &str = "((0 - 2147483647 - 1) * (2147483647 + 1) * 2)";
+
+/// `i64::MIN / -1` wraps to i64::MIN, so adding 1 exits with 1;
+/// `i64::MIN % -1` is 0, so adding 7 exits with 7.
+fn sources() -> [(String, i32); 2] {
+    [
+        (format!("kharrej {} / (0 - 1) + 1;", MIN), 1),
+        (format!("kharrej {} % (0 - 1) + 7;", MIN), 7),
+    ]
+}
+
+fn parse(source: &str) -> ria::ast::Program {
+    let Ok(Artifact::Ast(program)) = Compiler::new().source(source).emit(Emit::Ast).compile() else {
+        panic!("failed to compile {:?}", source);
+    };
+    program
+}
+
+#[test]
+fn bytecode_wraps_min_by_minus_one() {
+    for (source, expected) in sources() {
+        assert_eq!(bytecode::run(&bytecode::compile(&parse(&source))).unwrap(), expected);
+    }
+}
+
+#[test]
+fn jit_wraps_min_by_minus_one() {
+    if !cfg!(target_arch = "x86_64") {
+        return;
+    }
+    for (source, expected) in sources() {
+        assert_eq!(jit::run(&jit::encode(&parse(&source))).unwrap(), expected, "{}", source);
+    }
+}
+
+#[test]
+fn x86_64_skips_idiv_for_minus_one() {
+    let Ok(Artifact::Assembly(asm)) = Compiler::new().source(&sources()[0].0).compile() else {
+        panic!("failed to compile");
+    };
+    let guard = asm.find("cmp rcx, -1").expect("no -1 guard");
+    assert!(guard < asm.find("idiv rcx").unwrap(), "{}", asm);
+}
+
+#[test]
+fn llvm_ir_avoids_undefined_division() {
+    let dir = Scratch::new("division");
+
+    for (i, (source, expected)) in sources().into_iter().enumerate() {
+        let ir = llvm::emit_module(&Cfg::build(&parse(&source)), "main.ria", &Target::host());
+        assert!(ir.contains("icmp eq i64 %bin"), "{}", ir);
+
+        // Running the module is only possible where LLVM is installed.
+        let file = format!("division{}.ll", i);
+        dir.write(&file, &ir);
+        let Ok(status) = Command::new("lli").arg(dir.join(&file)).status() else {
+            continue;
+        };
+        assert_eq!(status.code(), Some(expected), "{}", ir);
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use ria::conditional::Conditions;
+use ria::include;
+use ria::limits::Limits;
+use ria::target::Target;
+
+use common::Scratch;
+
+#[test]
+fn includes_resolve_through_search_paths_and_reject_cycles() {
+    let dir = Scratch::new("include");
+    dir.write("lib/defs.ria", "kharrej 1;\n");
+    dir.write("a.ria", "jme3 \"b.ria\";\n");
+    dir.write("b.ria", "jme3 \"a.ria\";\n");
+
+    let none = Conditions::default();
+    let limits = Limits::default();
+    let lib = dir.join("lib").display().to_string();
+    let main = dir.join("main.ria");
+    let source = "jme3 \"defs.ria\";\nkharrej 2;\n";
+    let expanded = include::expand(source, &main, &[lib], &none, &limits).unwrap();
+    assert_eq!(expanded, "kharrej 1;\nkharrej 2;\n");
+
+    let a = dir.join("a.ria");
+    let err = include::expand(&dir.read("a.ria"), &a, &[], &none, &limits).unwrap_err();
+    assert!(err.message.starts_with("Include cycle"), "{}", err.message);
+}
+
+#[test]
+fn conditionals_are_evaluated_before_includes() {
+    let dir = Scratch::new("include-conditional");
+    dir.write("defs.ria", "kharrej 1;\n#ila DEMO\n#wla\n#wla\n#sala\n");
+
+    let main = dir.join("main.ria");
+    let source = "#ila DEMO\njme3 \"missing.ria\";\n#sala\nkharrej 2;\n";
+    let conditions = Conditions::new(&Target::host(), &[]);
+    let limits = Limits::default();
+    let expanded = include::expand(source, &main, &[], &conditions, &limits).unwrap();
+    assert_eq!(expanded, "\n\n\nkharrej 2;\n");
+
+    // Errors in an included file give its own name and line numbers.
+    let source = "kharrej 0;\njme3 \"defs.ria\";\n";
+    let err = include::expand(source, &main, &[], &conditions, &limits).unwrap_err();
+    assert_eq!(err.code, "E0011");
+    assert!(err.message.contains("defs.ria' line 4: "), "{}", err.message);
+}
@@ -0,0 +1,37 @@
+use ria::lexer::{LexError, Lexer, Span, TokenType};
+use ria::parser::Parser;
+use ria::{CompileError, Compiler};
+
+#[test]
+fn largest_literal_is_accepted() {
+    let tokens: Vec<_> = Lexer::new("kharrej 2147483647;").collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens[1].value.as_deref(), Some("2147483647"));
+}
+
+#[test]
+fn oversized_literal_reports_its_offset_and_the_allowed_range() {
+    let error = Lexer::new("kharrej 1 + 99999999999999999999;").find_map(Result::err).unwrap();
+    assert_eq!(
+        error,
+        LexError::NumberOutOfRange { literal: "99999999999999999999".to_string(), offset: 12 }
+    );
+
+    let result = Compiler::new().source("kharrej 2147483648;").compile();
+    let Err(CompileError::Lex(error)) = result else {
+        panic!("expected a lexing error");
+    };
+    assert_eq!(error.code, "E0005");
+    assert_eq!(error.span, Some(Span { start: 8, end: 18 }));
+    assert!(error.message.contains("range is 0 to 2147483647"), "{}", error.message);
+}
+
+#[test]
+fn parser_rejects_number_tokens_that_skipped_the_lexer_check() {
+    let mut tokens: Vec<_> = Lexer::new("kharrej 1;").collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens[1].token_type, TokenType::Number);
+    tokens[1].value = Some("2147483648".to_string());
+
+    let error = Parser::new(tokens).parse_program().unwrap_err();
+    assert_eq!(error.code, "E0005");
+    assert_eq!(error.span, Some(Span { start: 8, end: 9 }));
+}
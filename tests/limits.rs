@@ -0,0 +1,112 @@
+mod common;
+
+use ria::cli::Emit;
+use ria::diagnostics::Diagnostic;
+use ria::limits::Limits;
+use ria::{Artifact, Compiler};
+
+use common::Scratch;
+
+fn compile(source: &str, limits: Limits) -> Result<Artifact, Diagnostic> {
+    let compiler = Compiler::new().source(source).emit(Emit::Ast).limits(limits);
+    compiler.compile().map_err(Diagnostic::from)
+}
+
+#[test]
+fn deep_parentheses_hit_the_depth_limit_instead_of_the_stack() {
+    let source = format!("kharrej {}1{};", "(".repeat(100_000), ")".repeat(100_000));
+    let error = compile(&source, Limits::default()).unwrap_err();
+    assert_eq!(error.code, "E0019");
+    assert!(error.message.contains("--limit depth="), "{}", error.message);
+}
+
+#[test]
+fn long_operator_chains_count_towards_depth() {
+    let source = format!("kharrej 0{};", "+0".repeat(300));
+    assert_eq!(compile(&source, Limits::default()).unwrap_err().code, "E0019");
+
+    let mut limits = Limits::default();
+    limits.set("depth=400").unwrap();
+    assert!(compile(&source, limits).is_ok());
+}
+
+#[test]
+fn exponential_macros_stop_at_the_token_limit() {
+    let mut source = "makro A0 1\n".to_string();
+    for i in 1..40 {
+        source.push_str(&format!("makro A{} A{}+A{}\n", i, i - 1, i - 1));
+    }
+    source.push_str("kharrej A39;\n");
+
+    let error = compile(&source, Limits::default()).unwrap_err();
+    assert_eq!(error.code, "E0019");
+    assert!(error.message.contains("1048576 tokens"), "{}", error.message);
+}
+
+#[test]
+fn deeply_nested_macros_hit_the_depth_limit_instead_of_the_stack() {
+    let mut chain = "makro M0 1\n".to_string();
+    for i in 1..10_000 {
+        chain.push_str(&format!("makro M{} M{}\n", i, i - 1));
+    }
+    chain.push_str("kharrej M9999;\n");
+
+    let arguments = format!("makro E(x) x\nkharrej {}1{};\n", "E(".repeat(1000), ")".repeat(1000));
+
+    for source in [chain, arguments] {
+        let error = compile(&source, Limits::default()).unwrap_err();
+        assert_eq!(error.code, "E0019");
+        assert!(error.message.contains("--limit depth="), "{}", error.message);
+    }
+}
+
+#[test]
+fn lexing_stops_at_the_token_limit() {
+    let limits = Limits { tokens: 1000, ..Limits::default() };
+    let error = compile(&";".repeat(5000), limits).unwrap_err();
+    assert_eq!(error.code, "E0019");
+    assert!(error.message.contains("more than 1000 tokens"), "{}", error.message);
+}
+
+#[test]
+fn oversized_source_is_rejected() {
+    let limits = Limits { file_size: 8, ..Limits::default() };
+    assert_eq!(compile("kharrej 0;\n", limits).unwrap_err().code, "E0019");
+}
+
+#[test]
+fn limit_settings_are_validated() {
+    let mut limits = Limits::default();
+    assert!(limits.set("tokens").is_err());
+    assert!(limits.set("stack=1").is_err());
+    assert!(limits.set("depth=0").is_err());
+    limits.set("file-size=1024").unwrap();
+    assert_eq!(limits.file_size, 1024);
+}
+
+#[test]
+fn includes_are_held_to_the_size_limit_while_expanding() {
+    let dir = Scratch::new("limits-include");
+    let limits = Limits { file_size: 1024, ..Limits::default() };
+    let compile = |main: &str| {
+        let compiler = Compiler::new().source(dir.read(main)).path(dir.join(main)).limits(limits);
+        compiler.compile().map_err(Diagnostic::from).unwrap_err()
+    };
+
+    // One file that is too large on its own.
+    dir.write("big.ria", "kharrej 0;\n".repeat(200));
+    dir.write("one.ria", "jme3 \"big.ria\";\n");
+    let error = compile("one.ria");
+    assert_eq!(error.code, "E0019");
+    assert!(error.message.contains("big.ria' is 2200 bytes"), "{}", error.message);
+
+    // Small files that double with every level would reach 2^40 lines.
+    dir.write("d0.ria", "kharrej 0;\n");
+    for i in 1..=40 {
+        let include = format!("jme3 \"d{}.ria\";\n", i - 1);
+        dir.write(&format!("d{}.ria", i), include.repeat(2));
+    }
+    let error = compile("d40.ria");
+    assert_eq!(error.code, "E0019");
+    assert!(error.message.contains("with its includes is more than"), "{}", error.message);
+}
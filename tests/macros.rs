@@ -0,0 +1,47 @@
+use ria::ast::{Expr, Stmt};
+use ria::cli::Emit;
+use ria::lexer::Span;
+use ria::{Artifact, CompileError, Compiler};
+
+fn exit_codes(source: &str) -> Vec<i32> {
+    let Ok(Artifact::Ast(program)) = Compiler::new().source(source).emit(Emit::Ast).compile() else {
+        panic!("failed to compile {:?}", source);
+    };
+    program
+        .statements
+        .iter()
+        .map(|stmt| {
+            let Stmt::Return { value: Expr::Number { value, .. }, .. } = stmt else {
+                panic!("expected a constant exit code");
+            };
+            *value
+        })
+        .collect()
+}
+
+#[test]
+fn macros_expand_nested_uses() {
+    let source = "makro EXIT(code) kharrej code;\nmakro BOTH(a, b) EXIT(a) EXIT(b)\nBOTH(4, 5)\n";
+    assert_eq!(exit_codes(source), [4, 5]);
+}
+
+#[test]
+fn macro_does_not_expand_inside_itself() {
+    // The inner LOOP is left as a plain word, which is not a statement.
+    let result = Compiler::new().source("makro LOOP LOOP kharrej 3;\nLOOP\n").compile();
+    let Err(CompileError::Parse(error)) = result else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(error.code, "E0020");
+    assert!(error.message.starts_with("Unexpected 'LOOP'"), "{}", error.message);
+}
+
+#[test]
+fn wrong_argument_count_is_an_error() {
+    let result = Compiler::new().source("makro EXIT(c) kharrej c;\nEXIT(1, 2)\n").compile();
+    let Err(CompileError::Macro(error)) = result else {
+        panic!("expected a macro error");
+    };
+    assert_eq!(error.code, "E0009");
+    assert_eq!(error.span, Some(Span { start: 25, end: 29 }));
+}
@@ -0,0 +1,56 @@
+use ria::cli::Emit;
+use ria::lexer::Span;
+use ria::{Artifact, CompileError, Compiler, bytecode};
+
+fn run(source: &str) -> i32 {
+    let Ok(Artifact::Ast(program)) = Compiler::new().source(source).emit(Emit::Ast).compile() else {
+        panic!("failed to compile {:?}", source);
+    };
+    bytecode::run(&bytecode::compile(&program)).unwrap()
+}
+
+fn rendered(source: &str) -> String {
+    let Ok(Artifact::Ast(program)) = Compiler::new().source(source).emit(Emit::Ast).compile() else {
+        panic!("failed to compile {:?}", source);
+    };
+    program.statements[0].to_string()
+}
+
+#[test]
+fn multiplication_binds_tighter_than_addition() {
+    assert_eq!(rendered("kharrej 1 + 2 * 3 - 4 % 3;"), "kharrej ((1 + (2 * 3)) - (4 % 3));");
+    assert_eq!(run("kharrej 2 + 3 * 4;"), 14);
+}
+
+#[test]
+fn operators_are_left_associative() {
+    assert_eq!(rendered("kharrej 20 - 5 - 3;"), "kharrej ((20 - 5) - 3);");
+    assert_eq!(run("kharrej 100 / 5 / 2;"), 10);
+}
+
+#[test]
+fn division_by_zero_is_defined() {
+    assert_eq!(run("kharrej 7 / 0;"), 0);
+    assert_eq!(run("kharrej 7 % 0;"), 7);
+}
+
+#[test]
+fn missing_operand_is_an_error() {
+    let error = Compiler::new().source("kharrej 2 + ;").compile().unwrap_err();
+    assert!(matches!(error, CompileError::Parse(_)), "{:?}", error);
+    assert_eq!(error.code(), "E0015");
+    assert_eq!(error.span(), Some(Span { start: 12, end: 13 }));
+}
+
+#[test]
+fn parentheses_group_subexpressions() {
+    assert_eq!(rendered("kharrej (2 + 3) * 4;"), "kharrej ((2 + 3) * 4);");
+    assert_eq!(run("kharrej 2 * (3 + (4 - 1)) % 7;"), 5);
+}
+
+#[test]
+fn unclosed_parenthesis_reports_its_offset() {
+    let error = Compiler::new().source("kharrej (2 + 3 * 4;").compile().unwrap_err();
+    assert_eq!(error.code(), "E0016");
+    assert!(error.diagnostic().message.contains("byte 8"), "{}", error.diagnostic().message);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use common::{Scratch, zakaria};
+
+fn build(dir: &Path, args: &[&str]) -> Output {
+    zakaria(dir, &[&["build"], args].concat())
+}
+
+#[test]
+fn existing_outputs_need_force() {
+    let dir = Scratch::new("outputs");
+    dir.write("prog.ria", "kharrej 3;\n");
+    dir.write("prog.riac", "not ours");
+
+    let refused = build(&dir, &["prog.ria", "--emit", "bytecode"]);
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("--force"));
+    assert_eq!(dir.read("prog.riac"), "not ours");
+
+    let forced = build(&dir, &["prog.ria", "--emit", "bytecode", "--force"]);
+    assert!(forced.status.success());
+    assert!(fs::read(dir.join("prog.riac")).unwrap().starts_with(b"RIAC"));
+}
+
+#[test]
+fn outputs_never_overwrite_the_input() {
+    let dir = Scratch::new("outputs-input");
+    dir.write("prog", "kharrej 3;\n");
+
+    let refused = build(&dir, &["prog", "--force"]);
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("input file 'prog'"));
+    assert_eq!(dir.read("prog"), "kharrej 3;\n");
+}
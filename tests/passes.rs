@@ -0,0 +1,40 @@
+use ria::ast::{Expr, Program, Stmt};
+use ria::diagnostics::Diagnostic;
+use ria::passes::{Pass, PassManager};
+use ria::{Artifact, Compiler};
+
+/// Rejects programs that exit with anything other than zero.
+struct ZeroExitLint;
+
+impl Pass for ZeroExitLint {
+    fn name(&self) -> &str {
+        "zero-exit"
+    }
+
+    fn run_ast(&self, program: &mut Program) -> Result<(), Diagnostic> {
+        for stmt in &program.statements {
+            if let Stmt::Return { value: Expr::Number { value, .. }, .. } = stmt
+                && *value != 0
+            {
+                return Err(Diagnostic::new("L0001", "programs must exit with 0"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn lint_pass_rejects_program() {
+    let result = Compiler::new().source("kharrej 3;").pass(ZeroExitLint).compile();
+    assert_eq!(result.unwrap_err().code(), "L0001");
+}
+
+#[test]
+fn disabled_pass_does_not_run() {
+    let mut passes = PassManager::new();
+    passes.add(ZeroExitLint);
+    passes.set_enabled("zero-exit", false).unwrap();
+
+    let result = Compiler::new().source("kharrej 3;").passes(passes).compile();
+    assert!(matches!(result, Ok(Artifact::Assembly(_))));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::process::Command;
+
+use ria::lexer::Span;
+use ria::plugin::Plugin;
+use ria::{Artifact, Compiler};
+
+use common::Scratch;
+
+/// Rejects exit code 13 and marks the assembly it was given.
+const PLUGIN: &str = r#"
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+struct RiaPluginVTable {
+    uint32_t abi_version;
+    const char *name;
+    const char *(*check_return)(int32_t value, size_t offset);
+    char *(*transform_asm)(const char *asm_code);
+};
+
+static const char *check_return(int32_t value, size_t offset) {
+    (void)offset;
+    return value == 13 ? "13 is unlucky" : NULL;
+}
+
+static char *transform_asm(const char *asm_code) {
+    char *out = malloc(strlen(asm_code) + sizeof "; unlucky\n");
+    strcpy(out, asm_code);
+    return strcat(out, "; unlucky\n");
+}
+
+static const struct RiaPluginVTable vtable = {1, "unlucky", check_return, transform_asm};
+
+const struct RiaPluginVTable *ria_plugin_init(void) { return &vtable; }
+"#;
+
+#[test]
+fn plugins_check_and_transform_programs() {
+    let dir = Scratch::new("plugin");
+    dir.write("unlucky.c", PLUGIN);
+    let built = Command::new("cc")
+        .args(["-shared", "-fPIC", "-o", "libunlucky.so", "unlucky.c"])
+        .current_dir(&dir)
+        .status();
+    // Loading a plugin needs a C compiler to build one.
+    if !built.is_ok_and(|status| status.success()) {
+        return;
+    }
+    let plugin = || Plugin::load(dir.join("libunlucky.so").to_str().unwrap()).unwrap();
+
+    let error = Compiler::new().source("kharrej 13;").pass(plugin()).compile().unwrap_err();
+    assert_eq!(error.code(), "E0008");
+    assert_eq!(error.span(), Some(Span { start: 0, end: 11 }));
+    let message = error.to_string();
+    assert!(message.contains("13 is unlucky (reported by plugin 'unlucky')"), "{}", message);
+
+    let Ok(Artifact::Assembly(asm)) = Compiler::new().source("kharrej 12;").pass(plugin()).compile()
+    else {
+        panic!("the plugin should accept 12");
+    };
+    assert!(asm.ends_with("; unlucky\n"), "{}", asm);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::process::Command;
+
+use ria::project::Manifest;
+
+use common::{Scratch, zakaria};
+
+#[test]
+fn new_project_builds_and_cleans() {
+    let dir = Scratch::new("new");
+
+    assert!(zakaria(&dir, &["new", "hello"]).status.success());
+    let manifest = dir.read("hello/ria.toml");
+    assert!(manifest.contains("name = \"hello\""), "{}", manifest);
+    assert_eq!(dir.read("hello/src/main.ria"), "kharrej 0;\n");
+    assert!(dir.join("hello/.gitignore").is_file());
+
+    assert!(!zakaria(&dir, &["new", "hello"]).status.success());
+
+    let hello = dir.join("hello");
+    assert!(zakaria(&hello, &["build", "src/main.ria", "--emit", "bytecode"]).status.success());
+    assert!(hello.join("main.riac").is_file());
+
+    assert!(zakaria(&hello, &["clean"]).status.success());
+    assert!(!hello.join("main.riac").exists());
+    assert!(hello.join("src/main.ria").is_file());
+}
+
+#[test]
+fn git_dependencies_are_fetched_and_locked() {
+    if Command::new("git").arg("--version").output().is_err() {
+        return;
+    }
+    let dir = Scratch::new("deps");
+    dir.write("math/math.ria", "makro SEVEN 7\n");
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=ria", "-c", "user.email=ria@example.com"])
+            .args(args)
+            .current_dir(dir.join("math"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "seven"]);
+
+    assert!(zakaria(&dir, &["new", "app"]).status.success());
+    let app = dir.join("app");
+    let manifest = dir.read("app/ria.toml");
+    let url = dir.join("math").display().to_string();
+    let manifest = format!("{}\n[dependencies]\nmath = {{ git = \"{}\" }}\n", manifest, url);
+    dir.write("app/ria.toml", manifest);
+    dir.write("app/src/main.ria", "jme3 \"math.ria\";\nkharrej SEVEN;\n");
+
+    let build = || zakaria(&app, &["build", "src/main.ria", "--emit", "ast"]);
+    let first = build();
+    assert!(first.status.success(), "{}", String::from_utf8_lossy(&first.stderr));
+    assert!(String::from_utf8_lossy(&first.stdout).contains("(number 7)"));
+    let lock = dir.read("app/ria.lock");
+    assert!(lock.contains("commit = \""), "{}", lock);
+
+    // A new upstream commit is ignored while the lockfile pins the old one.
+    dir.write("math/math.ria", "makro SEVEN 8\n");
+    git(&["commit", "--quiet", "-am", "eight"]);
+    assert!(String::from_utf8_lossy(&build().stdout).contains("(number 7)"));
+    assert_eq!(dir.read("app/ria.lock"), lock);
+}
+
+#[test]
+fn dependency_values_are_read_as_quoted_strings() {
+    let text = "[package]\nname = \"app\"\n[dependencies]\n\
+                math = { git = \"/srv/a,b}\\\"c\", rev = \"v1\" }\n";
+    let manifest = Manifest::parse(text).unwrap();
+    assert_eq!(manifest.dependencies[0].git, "/srv/a,b}\"c");
+    assert_eq!(manifest.dependencies[0].rev.as_deref(), Some("v1"));
+
+    // Passed to git, this would be an option rather than a URL.
+    let hostile = "[package]\nname = \"app\"\n[dependencies]\n\
+                   math = { git = \"--upload-pack=touch /tmp/pwned\" }\n";
+    let error = Manifest::parse(hostile).unwrap_err();
+    assert!(error.contains("cannot start with '-'"), "{}", error);
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use std::fs;
+
+use common::{Scratch, zakaria};
+
+/// Builds `source` as `prog.ria` inside a fresh directory and returns it.
+fn build(source: &str, name: &str) -> (Scratch, bool) {
+    let dir = Scratch::new(&format!("repro-{}", name));
+    dir.write("prog.ria", source);
+
+    let status = zakaria(&dir, &["build", "prog.ria", "--coverage"]).status;
+    (dir, status.success())
+}
+
+fn read(dir: &Scratch, file: &str) -> Vec<u8> {
+    fs::read(dir.join(file)).unwrap_or_else(|e| panic!("reading {}: {}", file, e))
+}
+
+#[test]
+fn compiling_twice_produces_identical_output() {
+    let source = "kharrej 4;\nkharrej 2;\n";
+    let (first, first_ok) = build(source, "a");
+    let (second, second_ok) = build(source, "b");
+
+    assert_eq!(read(&first, "prog.asm"), read(&second, "prog.asm"));
+
+    // The executable can only be compared when nasm and ld are installed.
+    if first_ok && second_ok {
+        assert_eq!(read(&first, "prog.o"), read(&second, "prog.o"));
+        assert_eq!(read(&first, "prog"), read(&second, "prog"));
+    }
+}
@@ -0,0 +1,54 @@
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+
+use ria::runner::{self, Outcome};
+
+fn script(name: &str, body: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("ria-runner-{}-{}", name, process::id()));
+    fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn programs_past_their_timeout_are_killed() {
+    let path = script("hang", "sleep 30");
+    let started = Instant::now();
+
+    let timeout = Some(Duration::from_millis(200));
+    let outcome = runner::run(path.to_str().unwrap(), &[], timeout).unwrap();
+    assert!(matches!(outcome, Outcome::TimedOut), "{:?}", outcome);
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn programs_within_their_timeout_report_their_status() {
+    let path = script("exit", "exit 7");
+
+    let outcome = runner::run(path.to_str().unwrap(), &[], Some(Duration::from_secs(30))).unwrap();
+    let Outcome::Exited(status) = outcome else {
+        panic!("{:?}", outcome);
+    };
+    assert_eq!(status.code(), Some(7));
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn arguments_reach_the_program() {
+    let path = script("args", "exit $(( $1 + $2 ))");
+    let args = ["3".to_string(), "4".to_string()];
+
+    let Outcome::Exited(status) = runner::run(path.to_str().unwrap(), &args, None).unwrap() else {
+        panic!("a program without a timeout cannot time out");
+    };
+    assert_eq!(status.code(), Some(7));
+
+    let _ = fs::remove_file(path);
+}
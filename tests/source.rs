@@ -0,0 +1,30 @@
+mod common;
+
+use ria::conditional::Conditions;
+use ria::limits::Limits;
+use ria::{include, source};
+
+use common::Scratch;
+
+#[test]
+fn byte_order_mark_is_dropped() {
+    let text = source::decode(b"\xef\xbb\xbfkharrej 4;\n".to_vec(), "main.ria").unwrap();
+    assert_eq!(text, "kharrej 4;\n");
+}
+
+#[test]
+fn invalid_utf8_reports_the_offending_byte() {
+    let error = source::decode(b"kharrej \xe9;\n".to_vec(), "main.ria").unwrap_err();
+    assert_eq!(error.code, "E0018");
+    assert!(error.message.contains("0xe9 at byte 8"), "{}", error.message);
+
+    let dir = Scratch::new("source");
+    dir.write("latin1.ria", b"kharrej 1; \xff\n");
+
+    let main = dir.join("main.ria");
+    let source = "jme3 \"latin1.ria\";\n";
+    let (none, limits) = (Conditions::default(), Limits::default());
+    let error = include::expand(source, &main, &[], &none, &limits).unwrap_err();
+    assert_eq!(error.code, "E0018");
+    assert!(error.message.contains("latin1.ria"), "{}", error.message);
+}
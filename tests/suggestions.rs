@@ -0,0 +1,35 @@
+use ria::Compiler;
+use ria::diagnostics::{Diagnostic, closest};
+use ria::lexer::Span;
+
+fn diagnostic(source: &str) -> Diagnostic {
+    Compiler::new().source(source).compile().unwrap_err().into()
+}
+
+#[test]
+fn misspelled_keyword_suggests_the_closest() {
+    let error = diagnostic("kharej 0;");
+    assert_eq!(error.code, "E0017");
+    assert!(error.message.contains("did you mean 'kharrej'?"), "{}", error.message);
+
+    let error = diagnostic("kharrej waqtt();");
+    assert!(error.message.contains("did you mean 'waqt'?"), "{}", error.message);
+}
+
+#[test]
+fn distant_words_get_no_suggestion() {
+    assert_eq!(closest("kharjj", &["kharrej", "makro"]), Some("kharrej"));
+    assert_eq!(closest("foo", &["kharrej", "makro"]), None);
+
+    assert_eq!(diagnostic("kharrej foo;").code, "E0002");
+}
+
+#[test]
+fn distant_words_at_statement_start_are_rejected() {
+    let error = diagnostic("hello kharrej 3;");
+    assert_eq!(error.code, "E0020");
+    assert_eq!(error.span, Some(Span { start: 0, end: 5 }));
+    assert!(!error.message.contains("did you mean"), "{}", error.message);
+
+    assert_eq!(diagnostic("kharrej 3; ;").code, "E0020");
+}
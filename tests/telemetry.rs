@@ -0,0 +1,35 @@
+mod common;
+
+use common::{Scratch, zakaria};
+
+fn project(name: &str, source: &str) -> Scratch {
+    let dir = Scratch::new(&format!("telemetry-{}", name));
+    dir.write("prog.ria", source);
+    dir
+}
+
+fn report(dir: &Scratch) -> String {
+    zakaria(dir, &["build", "prog.ria", "--emit", "bytecode", "--stats-json", "report.json"]);
+    dir.read("report.json")
+}
+
+#[test]
+fn stats_json_reports_passes_and_artifacts() {
+    let dir = project("ok", "kharrej 3;\n");
+    let report = report(&dir);
+
+    assert!(report.contains("\"success\": true"), "{}", report);
+    assert!(report.contains("{\"name\": \"parsing\", \"seconds\": "), "{}", report);
+    assert!(report.contains("\"errors\": 0"), "{}", report);
+    assert!(report.contains("\"path\": \"prog.riac\""), "{}", report);
+}
+
+#[test]
+fn stats_json_reports_failed_builds() {
+    let dir = project("error", "kharrej;\n");
+    let report = report(&dir);
+
+    assert!(report.contains("\"success\": false"), "{}", report);
+    assert!(report.contains("\"codes\": [\"E0003\"]"), "{}", report);
+    assert!(report.contains("\"artifacts\": []"), "{}", report);
+}
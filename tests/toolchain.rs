@@ -0,0 +1,41 @@
+mod common;
+
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+use ria::target::Target;
+use ria::toolchain::{self, ToolchainError};
+
+use common::Scratch;
+
+#[test]
+fn toolchain_errors_say_which_step_failed() {
+    let missing = env::temp_dir().join(format!("ria-toolchain-{}/prog", process::id()));
+    let error = toolchain::assemble("", missing.to_str().unwrap(), &Target::host()).unwrap_err();
+    assert!(matches!(error, ToolchainError::Write { .. }), "{:?}", error);
+    assert!(std::error::Error::source(&error).is_some());
+
+    let error = toolchain::archive("does-not-exist.o", "ria-toolchain-test").unwrap_err();
+    let ToolchainError::Failed { tool, .. } = error else {
+        panic!("{:?}", error);
+    };
+    assert_eq!(tool, "ar");
+}
+
+#[test]
+fn bundled_tools_are_preferred() {
+    let prefix = Scratch::new("bundle");
+    prefix.write("libexec/ria/nasm", "#!/bin/sh\n");
+    fs::create_dir_all(prefix.join("bin")).unwrap();
+    let compiler = prefix.join("bin/zakaria");
+    fs::copy(env!("CARGO_BIN_EXE_MyCompiler"), &compiler).unwrap();
+
+    let output = Command::new(&compiler)
+        .args(["toolchain", "check", "--target", "x86_64-unknown-linux-gnu"])
+        .output()
+        .unwrap();
+    let report = String::from_utf8(output.stdout).unwrap();
+    let bundled = prefix.join("libexec/ria/nasm").display().to_string();
+    assert!(report.lines().any(|line| line.starts_with("nasm") && line.ends_with(&bundled)));
+}